@@ -35,17 +35,6 @@ fi
 PLAYER2_PK=$(solana-keygen pubkey "$PLAYER2_KEYPAIR")
 echo "PLAYER2_PK:  $PLAYER2_PK"
 
-# =========================
-# ESCROW authority keypair
-# =========================
-ESCROW_KEYPAIR="$WALLETS_DIR/escrow.json"
-if [ ! -f "$ESCROW_KEYPAIR" ]; then
-  echo "Generating keypair: escrow.json"
-  solana-keygen new --no-bip39-passphrase --outfile "$ESCROW_KEYPAIR" >/dev/null
-fi
-ESCROW_AUTH=$(solana-keygen pubkey "$ESCROW_KEYPAIR")
-echo "ESCROW_AUTH: $ESCROW_AUTH"
-
 # =========================
 # Create MINT (6 decimals)
 # =========================
@@ -99,10 +88,8 @@ mint_to() {
 echo "Calculating  ATAs…"
 PAYER_ATA=$(calc_ata "$MINT" "$PAYER_PK")
 PLAYER2_ATA=$(calc_ata "$MINT" "$PLAYER2_PK")
-ESCROW_ATA=$(calc_ata "$MINT" "$ESCROW_AUTH")
 echo "PAYER_ATA:   $PAYER_ATA"
 echo "PLAYER2_ATA: $PLAYER2_ATA"
-echo "ESCROW_ATA:  $ESCROW_ATA"
 
 # =========================
 # Create ATAs (payer is fee-payer for everyone)
@@ -110,7 +97,6 @@ echo "ESCROW_ATA:  $ESCROW_ATA"
 echo "Creating ATAs…"
 create_ata "$MINT" "$PAYER_PK"   "$PAYER_KEYPAIR"
 create_ata "$MINT" "$PLAYER2_PK" "$PAYER_KEYPAIR"
-create_ata "$MINT" "$ESCROW_AUTH" "$PAYER_KEYPAIR"
 
 # =========================
 # Mint tokens
@@ -145,7 +131,6 @@ CLUSTER=$CLUSTER
 # Wallets
 PATH_TO_YOUR_SOLANA_PAYER_JSON=$PAYER_KEYPAIR
 PATH_TO_YOUR_SOLANA_PLAYER2_JSON=$PLAYER2_KEYPAIR
-PATH_TO_YOUR_SOLANA_GAME_JSON=$ESCROW_KEYPAIR
 
 # Program (fill in if necessary)
 DEPLOYED_PROGRAM_ADDRESS=${DEPLOYED_PROGRAM_ADDRESS:-}
@@ -154,7 +139,6 @@ DEPLOYED_PROGRAM_ADDRESS=${DEPLOYED_PROGRAM_ADDRESS:-}
 MINT=$MINT
 PAYER_TOKEN_ACCOUNT=$PAYER_ATA
 PLAYER2_TOKEN_ACCOUNT=$PLAYER2_ATA
-SCROW_TOKEN_ACCOUNT=$ESCROW_ATA
 
 # Pyth (ETH/USDC devnet)
 PYTH_PRICE_ETH_USDC=EdVCmQ9FSPcVe5YySXDPCRmc8aDQLKJ9xvYBMZPie1Vw
@@ -164,4 +148,3 @@ echo "Ready. Variables saved in .env"
 echo "   - MINT:                 $MINT"
 echo "   - PAYER_TOKEN_ACCOUNT:  $PAYER_ATA"
 echo "   - PLAYER2_TOKEN_ACCOUNT:$PLAYER2_ATA"
-echo "   - SCROW_TOKEN_ACCOUNT:  $ESCROW_ATA"
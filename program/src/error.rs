@@ -0,0 +1,182 @@
+// The derived impls below still name the deprecated variants.
+#![allow(deprecated)]
+
+use num_derive::FromPrimitive;
+use solana_program::{
+    decode_error::DecodeError,
+    msg,
+    program_error::{PrintProgramError, ProgramError},
+};
+use thiserror::Error;
+
+/// Errors returned by the escrow program.
+///
+/// The discriminant of each variant is the custom error code seen by clients,
+/// so variants must only ever be appended. Variants that are no longer
+/// returned are deprecated rather than removed.
+#[derive(Clone, Debug, Eq, Error, FromPrimitive, PartialEq)]
+pub enum EscrowError {
+    /// 0. The instruction data could not be decoded
+    #[error("Invalid instruction")]
+    InvalidInstruction,
+    /// 1. The price moved too much since Player 1 entered
+    #[error("Price fluctuation is too high to join the game")]
+    PriceFluctuationTooHigh,
+    /// 2. The game is no longer active
+    #[error("Game is inactive")]
+    GameInactive,
+    /// 3. Unused, status checks return `InvalidGameStatus`
+    #[deprecated(note = "never returned; the code is kept reserved")]
+    #[error("Game is still active")]
+    GameStillActive,
+    /// 4. Unused, status checks return `InvalidGameStatus`
+    #[deprecated(note = "never returned; the code is kept reserved")]
+    #[error("Player 2 has already joined the game")]
+    Player2AlreadyJoined,
+    /// 5. Unused, status checks return `InvalidGameStatus`
+    #[deprecated(note = "never returned; the code is kept reserved")]
+    #[error("Player 2 has not joined the game")]
+    Player2Missing,
+    /// 6. Unused, settlement decides the winner or refunds a draw
+    #[deprecated(note = "never returned; the code is kept reserved")]
+    #[error("Game has no winner")]
+    NoWinner,
+    /// 7. None of the token accounts passed belongs to the winner
+    #[error("Winner token account not found")]
+    WinnerAccountMismatch,
+    /// 8. The oracle account is not a valid price feed
+    #[error("Invalid oracle account")]
+    InvalidOracleAccount,
+    /// 9. The oracle has no current price
+    #[error("Oracle price unavailable")]
+    PriceUnavailable,
+    /// 10. A token account is not owned by the expected player
+    #[error("Token account owner mismatch")]
+    TokenAccountOwnerMismatch,
+    /// 11. The escrow token account is not the one recorded for the game
+    #[error("Escrow token account mismatch")]
+    EscrowTokenAccountMismatch,
+    /// 12. The signer is not allowed to perform this action
+    #[error("Unauthorized signer")]
+    Unauthorized,
+    /// 13. The vault authority is not the PDA derived for the game
+    #[error("Invalid vault authority")]
+    InvalidVaultAuthority,
+    /// 14. The game account is not the PDA derived from Player 1 and the nonce
+    #[error("Invalid game account")]
+    InvalidGameAccount,
+    /// 15. The stake does not match the one recorded for the game
+    #[error("Stake amount mismatch")]
+    StakeMismatch,
+    /// 16. The stake must be greater than zero
+    #[error("Invalid stake amount")]
+    InvalidStake,
+    /// 17. A mint or token account does not match the game's stake mint
+    #[error("Mint mismatch")]
+    MintMismatch,
+    /// 18. The token program is not SPL Token or Token-2022, or does not own the mint
+    #[error("Invalid token program")]
+    InvalidTokenProgram,
+    /// 19. The game has not reached its expiry yet
+    #[error("Game has not expired")]
+    GameNotExpired,
+    /// 20. The game is past its expiry and can only be refunded
+    #[error("Game has expired")]
+    GameExpired,
+    /// 21. The game duration must be greater than zero
+    #[error("Invalid game duration")]
+    InvalidDuration,
+    /// 22. Player 2 can no longer join, the join deadline has passed
+    #[error("Join deadline has passed")]
+    JoinDeadlinePassed,
+    /// 23. Player 2 can still join, the join deadline has not passed
+    #[error("Join deadline has not passed")]
+    JoinDeadlineNotPassed,
+    /// 24. The join window must be greater than zero and no longer than the game duration
+    #[error("Invalid join window")]
+    InvalidJoinWindow,
+    /// 25. The oracle price was published too long ago
+    #[error("Oracle price is stale")]
+    StalePrice,
+    /// 26. The oracle confidence interval is too wide relative to the price
+    #[error("Oracle price is too uncertain")]
+    PriceTooUncertain,
+    /// 27. The maximum price age or confidence ratio is out of range
+    #[error("Invalid oracle parameters")]
+    InvalidOracleParameters,
+    /// 28. The oracle price is zero or negative
+    #[error("Oracle price is not positive")]
+    NonPositivePrice,
+    /// 29. The oracle account is not the feed pinned for the game
+    #[error("Oracle account mismatch")]
+    OracleMismatch,
+    /// 30. A caller-supplied price was passed to a build without price overrides
+    #[error("Price overrides are disabled")]
+    PriceOverrideDisabled,
+    /// 31. The market does not exist or is not active
+    #[error("Invalid market")]
+    InvalidMarket,
+    /// 32. The market registry has no room for another market
+    #[error("Market registry is full")]
+    MarketRegistryFull,
+    /// 33. The market's join drift or win threshold is outside the program-wide bounds
+    #[error("Invalid market parameters")]
+    InvalidMarketParameters,
+    /// 34. The market registry is not the program's registry PDA
+    #[error("Invalid market registry")]
+    InvalidMarketRegistry,
+    /// 35. The game's join drift or win threshold is outside the program-wide bounds
+    #[error("Invalid game thresholds")]
+    InvalidGameThresholds,
+    /// 36. A price, amount or timestamp computation left the range of its type
+    #[error("Arithmetic overflow")]
+    ArithmeticOverflow,
+    /// 37. The game's status does not allow this instruction
+    #[error("Invalid game status")]
+    InvalidGameStatus,
+    /// 38. The config account is not the program's config PDA
+    #[error("Invalid config")]
+    InvalidConfig,
+    /// 39. The program is paused, games can only be refunded
+    #[error("Program is paused")]
+    ProgramPaused,
+    /// 40. The stake mint is not one of the mints allowed by the config
+    #[error("Mint not allowed")]
+    MintNotAllowed,
+    /// 41. A default threshold, the fee, the allowed mints or the timelock delay are out of range
+    #[error("Invalid config parameters")]
+    InvalidConfigParameters,
+    /// 42. The fee token account is not owned by the config's treasury
+    #[error("Treasury token account mismatch")]
+    TreasuryMismatch,
+    /// 43. There is no queued update or proposed admin to act on
+    #[error("Nothing pending")]
+    NothingPending,
+    /// 44. The queued update's timelock has not elapsed yet
+    #[error("Timelock has not elapsed")]
+    TimelockNotElapsed,
+    /// 45. The account receiving a finished game's rent is not Player 1
+    #[error("Rent recipient mismatch")]
+    RentRecipientMismatch,
+    /// 46. The account is not the program's ProgramData account
+    #[error("Invalid program data account")]
+    InvalidProgramData,
+}
+
+impl From<EscrowError> for ProgramError {
+    fn from(e: EscrowError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+impl<T> DecodeError<T> for EscrowError {
+    fn type_of() -> &'static str {
+        "EscrowError"
+    }
+}
+
+impl PrintProgramError for EscrowError {
+    fn print<E>(&self) {
+        msg!("Error: {}", self);
+    }
+}
@@ -0,0 +1,810 @@
+use crate::{
+    error::EscrowError,
+    oracle::OracleType,
+    pda::{
+        find_config_address, find_game_address, find_market_registry_address,
+        find_mock_price_address, find_program_data_address, find_vault_authority,
+        get_vault_address,
+    },
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// Instructions supported by the escrow program.
+///
+/// The variant order is the wire discriminant (first byte of the Borsh
+/// encoding), so new variants must only ever be appended.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub enum EscrowInstruction {
+    /// Player 1 opens a game, creates its vault and deposits their stake into it.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Player 1 (payer)
+    /// 1. `[writable]` Escrow account that will hold the game state, `[b"game", player 1, nonce]`
+    /// 2. `[writable]` Escrow token account, the vault authority's associated token account,
+    ///    created unless it already exists
+    /// 3. `[]` Vault authority PDA, `[b"vault", escrow account]`
+    /// 4. `[]` Stake mint
+    /// 5. `[writable]` Player 1's token account
+    /// 6. `[]` SPL Token or Token-2022 program, must own the stake mint
+    /// 7. `[]` Associated token account program
+    /// 8. `[]` Price feed approved for the market, pinned for the game's lifetime
+    /// 9. `[]` System program
+    /// 10. `[]` Market registry, `[b"markets"]`
+    /// 11. `[]` Program config, `[b"config"]`, must not be paused and must allow the stake mint
+    CreateGame {
+        /// `true` if Player 1 bets on an increase, `false` for a decrease
+        choice: bool,
+        /// Entry price with 8 decimals, `0` to read it from the oracle
+        entry_price: u64,
+        /// Client-chosen seed distinguishing the games of the same Player 1
+        nonce: u64,
+        /// Amount each player stakes, in the mint's smallest units
+        stake: u64,
+        /// Seconds from creation after which an unsettled game can be refunded as a draw
+        duration: i64,
+        /// Seconds from creation during which Player 2 can join, at most `duration`
+        join_window: i64,
+        /// Oldest oracle price accepted for this game, in seconds
+        max_price_age: u64,
+        /// Widest oracle confidence interval accepted, in basis points of the price
+        max_confidence_bps: u16,
+        /// Registry id of the market the game is played on
+        market_id: u8,
+        /// Largest move from the entry price at which Player 2 can still join, in
+        /// basis points, `0` for the market's default
+        max_join_drift_bps: u16,
+        /// Move from the entry price that decides the game, in basis points, `0` for
+        /// the market's default
+        win_threshold_bps: u16,
+    },
+
+    /// Reads the oracle and stores the price, normalised to 8 decimals, as the
+    /// game's `last_price`, rejecting prices that are not positive or are older
+    /// or less certain than the game allows.
+    ///
+    /// Accounts expected:
+    /// 0. `[]` Price feed account pinned for the game
+    /// 1. `[writable]` Escrow account holding the game state
+    /// 2. `[]` Program config, must not be paused
+    FetchPrice,
+
+    /// Player 2 joins an open game, matching Player 1's stake.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Player 2
+    /// 1. `[writable]` Escrow account holding the game state
+    /// 2. `[writable]` Escrow token account
+    /// 3. `[]` Stake mint
+    /// 4. `[writable]` Player 2's token account
+    /// 5. `[]` SPL Token or Token-2022 program, must own the stake mint
+    /// 6. `[]` Price feed account pinned for the game
+    /// 7. `[]` Program config, must not be paused
+    JoinGame {
+        /// Current price with 8 decimals, `0` to read it from the oracle
+        last_price: u64,
+        /// Must equal the stake recorded by Player 1
+        stake: u64,
+    },
+
+    /// Decides the winner once the price has moved by the game's win threshold
+    /// either way.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` Escrow account holding the game state
+    /// 1. `[]` Player 1's token account, owned by Player 1 and holding the stake mint
+    /// 2. `[]` Player 2's token account, owned by Player 2 and holding the stake mint
+    /// 3. `[]` Price feed account pinned for the game
+    /// 4. `[]` Program config, must not be paused
+    SettleGame {
+        /// Current price with 8 decimals, `0` to read it from the oracle
+        last_price: u64,
+    },
+
+    /// Refunds Player 1's stake while nobody has joined the game, then closes
+    /// the emptied vault and the game account and returns their rent to Player 1.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Player 1, receiving the rent of the vault and the game account
+    /// 1. `[writable]` Escrow account holding the game state
+    /// 2. `[]` Vault authority PDA
+    /// 3. `[writable]` Escrow token account
+    /// 4. `[writable]` Stake mint, receiving any Token-2022 fees withheld in the vault
+    /// 5. `[writable]` Player 1's token account
+    /// 6. `[]` SPL Token or Token-2022 program, must own the stake mint
+    WithdrawFunds,
+
+    /// Pays the whole vault balance out of a settled game: the protocol fee
+    /// recorded at creation to the treasury and the rest to the winner. The
+    /// emptied vault and the game account are then closed and their rent
+    /// returned to Player 1.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` Escrow account holding the game state
+    /// 1. `[]` Vault authority PDA
+    /// 2. `[writable]` Escrow token account
+    /// 3. `[writable]` Stake mint, receiving any Token-2022 fees withheld in the vault
+    /// 4. `[writable]` Winner's token account
+    /// 5. `[]` SPL Token or Token-2022 program, must own the stake mint
+    /// 6. `[]` Program config, must not be paused
+    /// 7. `[writable]` Token account of the config's treasury, receiving the protocol fee
+    /// 8. `[writable]` Player 1, receiving the rent of the vault and the game account
+    CloseGame,
+
+    /// Refunds both stakes of a matched game that expired without a winner,
+    /// then closes the emptied vault and the game account and returns their
+    /// rent to Player 1. Anyone can send it.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` Escrow account holding the game state
+    /// 1. `[]` Vault authority PDA
+    /// 2. `[writable]` Escrow token account
+    /// 3. `[writable]` Stake mint, receiving any Token-2022 fees withheld in the vault
+    /// 4. `[writable]` Player 1's token account
+    /// 5. `[writable]` Player 2's token account
+    /// 6. `[]` SPL Token or Token-2022 program, must own the stake mint
+    /// 7. `[writable]` Player 1, receiving the rent of the vault and the game account
+    RefundDraw,
+
+    /// Refunds Player 1's stake once the join deadline has passed without
+    /// Player 2 joining, then closes the emptied vault and the game account and
+    /// returns their rent to Player 1. Anyone can send it.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` Escrow account holding the game state
+    /// 1. `[]` Vault authority PDA
+    /// 2. `[writable]` Escrow token account
+    /// 3. `[writable]` Stake mint, receiving any Token-2022 fees withheld in the vault
+    /// 4. `[writable]` Player 1's token account, owned by Player 1
+    /// 5. `[]` SPL Token or Token-2022 program, must own the stake mint
+    /// 6. `[writable]` Player 1, receiving the rent of the vault and the game account
+    RefundUnjoined,
+
+    /// Creates or updates the signer's mock price feed. Only available in
+    /// builds with the `mock-oracle` feature, which read such feeds instead of Pyth.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Feed authority, pays for the feed on first use
+    /// 1. `[writable]` Mock price feed, `[b"mock_price", authority]`
+    /// 2. `[]` System program
+    SetMockPrice {
+        /// Price mantissa, as in a Pyth feed
+        price: i64,
+        /// Confidence interval, with the same exponent as the price
+        conf: u64,
+        /// Exponent of `price` and `conf`
+        expo: i32,
+    },
+
+    /// Creates the program's market registry, managed by the config's admin.
+    /// Only the program's upgrade authority can send it.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Program upgrade authority (payer)
+    /// 1. `[writable]` Market registry, `[b"markets"]`
+    /// 2. `[]` System program
+    /// 3. `[]` ProgramData account of the program
+    InitializeMarketRegistry,
+
+    /// Queues a market on an approved price feed, registered by
+    /// `ApplyMarketUpdate` once the config's timelock has elapsed. The new
+    /// market's id is the number of markets registered before it. Only the
+    /// admin can send it.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Market registry
+    /// 2. `[]` Price feed account of `oracle_type` for the market's asset
+    /// 3. `[]` Program config, providing the default thresholds
+    AddMarket {
+        /// Asset symbol, e.g. `b"BTC/USD\0"`, zero-padded
+        symbol: [u8; 8],
+        /// Kind of feed the oracle account is
+        oracle_type: OracleType,
+        /// Largest move from the entry price at which Player 2 can still join, in basis
+        /// points, `0` for the config's default
+        max_join_drift_bps: u16,
+        /// Move from the entry price that decides the game, in basis points, `0` for the
+        /// config's default
+        win_threshold_bps: u16,
+    },
+
+    /// Queues new default parameters for a market or reactivates it, applied by
+    /// `ApplyMarketUpdate` once the config's timelock has elapsed. Deactivating a
+    /// market applies immediately. Games already created keep the parameters they
+    /// started with. Only the admin can send it.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Market registry
+    /// 2. `[]` Program config
+    UpdateMarket {
+        /// Registry id of the market
+        market_id: u8,
+        /// Largest move from the entry price at which Player 2 can still join, in basis points
+        max_join_drift_bps: u16,
+        /// Move from the entry price that decides the game, in basis points
+        win_threshold_bps: u16,
+        /// `false` stops new games from being created on the market
+        active: bool,
+    },
+
+    /// Creates the program config, with the signer as admin and any mint allowed.
+    /// Only the program's upgrade authority can send it.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Program upgrade authority, becomes the admin (payer)
+    /// 1. `[writable]` Program config, `[b"config"]`
+    /// 2. `[]` System program
+    /// 3. `[]` ProgramData account of the program
+    InitializeConfig {
+        /// Join drift of markets registered without one, in basis points
+        default_max_join_drift_bps: u16,
+        /// Win threshold of markets registered without one, in basis points
+        default_win_threshold_bps: u16,
+        /// Protocol fee taken from payouts, in basis points
+        fee_bps: u16,
+        /// Owner of the token accounts receiving the protocol fee
+        treasury: Pubkey,
+        /// Seconds config and market updates wait before they can be applied
+        timelock_delay: i64,
+    },
+
+    /// Queues new settings for the program config, applied by
+    /// `ApplyConfigUpdate` once the timelock in force has elapsed. Games
+    /// already created keep the fee and thresholds they started with. Only the
+    /// admin can send it.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Program config
+    UpdateConfig {
+        /// Mints games can be staked in, empty to allow any mint
+        allowed_mints: Vec<Pubkey>,
+        /// Join drift of markets registered without one, in basis points
+        default_max_join_drift_bps: u16,
+        /// Win threshold of markets registered without one, in basis points
+        default_win_threshold_bps: u16,
+        /// Protocol fee taken from payouts, in basis points
+        fee_bps: u16,
+        /// Owner of the token accounts receiving the protocol fee
+        treasury: Pubkey,
+        /// Seconds config and market updates wait before they can be applied
+        timelock_delay: i64,
+    },
+
+    /// Pauses or resumes the program. While paused, games cannot be created,
+    /// joined, priced, settled or paid out, but can still be refunded. Only
+    /// the admin can send it.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Program config
+    SetPaused {
+        /// `true` to pause, `false` to resume
+        paused: bool,
+    },
+
+    /// Proposes the next admin, who takes over once they send `AcceptAdmin`.
+    /// Only the admin can send it.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Program config
+    ProposeAdmin {
+        /// Proposed admin, the default key to withdraw a proposal
+        new_admin: Pubkey,
+    },
+
+    /// Makes the signer the admin. Only the proposed admin can send it.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Proposed admin
+    /// 1. `[writable]` Program config
+    AcceptAdmin,
+
+    /// Puts the settings queued by `UpdateConfig` in force once their timelock
+    /// has elapsed. Anyone can send it.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` Program config
+    ApplyConfigUpdate,
+
+    /// Applies the market change queued by `AddMarket` or `UpdateMarket` once
+    /// its timelock has elapsed. Anyone can send it.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` Market registry
+    ApplyMarketUpdate,
+
+    /// Drops the queued config update and market change. Only the admin can send it.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Program config
+    /// 2. `[writable]` Market registry
+    CancelPendingUpdates,
+}
+
+impl EscrowInstruction {
+    /// Decodes an instruction, rejecting unknown variants and any
+    /// missing or trailing bytes.
+    pub fn unpack(instruction_data: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(instruction_data).map_err(|_| EscrowError::InvalidInstruction.into())
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        // Serializing into a Vec cannot fail
+        self.try_to_vec().unwrap()
+    }
+}
+
+/// Creates a `CreateGame` instruction for the game account derived from `payer` and `nonce`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_game(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    payer_token_account: &Pubkey,
+    token_program: &Pubkey,
+    oracle_account: &Pubkey,
+    choice: bool,
+    entry_price: u64,
+    nonce: u64,
+    stake: u64,
+    duration: i64,
+    join_window: i64,
+    max_price_age: u64,
+    max_confidence_bps: u16,
+    market_id: u8,
+    max_join_drift_bps: u16,
+    win_threshold_bps: u16,
+) -> Instruction {
+    let (escrow_account, _) = find_game_address(program_id, payer, nonce);
+    let (vault_authority, _) = find_vault_authority(program_id, &escrow_account);
+    let escrow_token_account = get_vault_address(program_id, &escrow_account, mint, token_program);
+    let (registry_account, _) = find_market_registry_address(program_id);
+    let (config_account, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(escrow_account, false),
+            AccountMeta::new(escrow_token_account, false),
+            AccountMeta::new_readonly(vault_authority, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*payer_token_account, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(*oracle_account, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(registry_account, false),
+            AccountMeta::new_readonly(config_account, false),
+        ],
+        data: EscrowInstruction::CreateGame {
+            choice,
+            entry_price,
+            nonce,
+            stake,
+            duration,
+            join_window,
+            max_price_age,
+            max_confidence_bps,
+            market_id,
+            max_join_drift_bps,
+            win_threshold_bps,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `FetchPrice` instruction.
+pub fn fetch_price(
+    program_id: &Pubkey,
+    oracle_account: &Pubkey,
+    escrow_account: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*oracle_account, false),
+            AccountMeta::new(*escrow_account, false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        ],
+        data: EscrowInstruction::FetchPrice.pack(),
+    }
+}
+
+/// Creates a `JoinGame` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn join_game(
+    program_id: &Pubkey,
+    player2: &Pubkey,
+    escrow_account: &Pubkey,
+    escrow_token_account: &Pubkey,
+    mint: &Pubkey,
+    player2_token_account: &Pubkey,
+    token_program: &Pubkey,
+    oracle_account: &Pubkey,
+    last_price: u64,
+    stake: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*player2, true),
+            AccountMeta::new(*escrow_account, false),
+            AccountMeta::new(*escrow_token_account, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*player2_token_account, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(*oracle_account, false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        ],
+        data: EscrowInstruction::JoinGame { last_price, stake }.pack(),
+    }
+}
+
+/// Creates a `SettleGame` instruction.
+pub fn settle_game(
+    program_id: &Pubkey,
+    escrow_account: &Pubkey,
+    player1_token_account: &Pubkey,
+    player2_token_account: &Pubkey,
+    oracle_account: &Pubkey,
+    last_price: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*escrow_account, false),
+            AccountMeta::new_readonly(*player1_token_account, false),
+            AccountMeta::new_readonly(*player2_token_account, false),
+            AccountMeta::new_readonly(*oracle_account, false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        ],
+        data: EscrowInstruction::SettleGame { last_price }.pack(),
+    }
+}
+
+/// Creates a `WithdrawFunds` instruction.
+pub fn withdraw_funds(
+    program_id: &Pubkey,
+    player1: &Pubkey,
+    escrow_account: &Pubkey,
+    escrow_token_account: &Pubkey,
+    mint: &Pubkey,
+    player1_token_account: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    let (vault_authority, _) = find_vault_authority(program_id, escrow_account);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*player1, true),
+            AccountMeta::new(*escrow_account, false),
+            AccountMeta::new_readonly(vault_authority, false),
+            AccountMeta::new(*escrow_token_account, false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new(*player1_token_account, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: EscrowInstruction::WithdrawFunds.pack(),
+    }
+}
+
+/// Creates a `CloseGame` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn close_game(
+    program_id: &Pubkey,
+    escrow_account: &Pubkey,
+    escrow_token_account: &Pubkey,
+    mint: &Pubkey,
+    winner_token_account: &Pubkey,
+    token_program: &Pubkey,
+    treasury_token_account: &Pubkey,
+    player1: &Pubkey,
+) -> Instruction {
+    let (vault_authority, _) = find_vault_authority(program_id, escrow_account);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*escrow_account, false),
+            AccountMeta::new_readonly(vault_authority, false),
+            AccountMeta::new(*escrow_token_account, false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new(*winner_token_account, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+            AccountMeta::new(*treasury_token_account, false),
+            AccountMeta::new(*player1, false),
+        ],
+        data: EscrowInstruction::CloseGame.pack(),
+    }
+}
+
+/// Creates a `RefundDraw` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn refund_draw(
+    program_id: &Pubkey,
+    escrow_account: &Pubkey,
+    escrow_token_account: &Pubkey,
+    mint: &Pubkey,
+    player1_token_account: &Pubkey,
+    player2_token_account: &Pubkey,
+    token_program: &Pubkey,
+    player1: &Pubkey,
+) -> Instruction {
+    let (vault_authority, _) = find_vault_authority(program_id, escrow_account);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*escrow_account, false),
+            AccountMeta::new_readonly(vault_authority, false),
+            AccountMeta::new(*escrow_token_account, false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new(*player1_token_account, false),
+            AccountMeta::new(*player2_token_account, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new(*player1, false),
+        ],
+        data: EscrowInstruction::RefundDraw.pack(),
+    }
+}
+
+/// Creates a `RefundUnjoined` instruction.
+pub fn refund_unjoined(
+    program_id: &Pubkey,
+    escrow_account: &Pubkey,
+    escrow_token_account: &Pubkey,
+    mint: &Pubkey,
+    player1_token_account: &Pubkey,
+    token_program: &Pubkey,
+    player1: &Pubkey,
+) -> Instruction {
+    let (vault_authority, _) = find_vault_authority(program_id, escrow_account);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*escrow_account, false),
+            AccountMeta::new_readonly(vault_authority, false),
+            AccountMeta::new(*escrow_token_account, false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new(*player1_token_account, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new(*player1, false),
+        ],
+        data: EscrowInstruction::RefundUnjoined.pack(),
+    }
+}
+
+/// Creates a `SetMockPrice` instruction for the mock feed of `authority`.
+pub fn set_mock_price(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    price: i64,
+    conf: u64,
+    expo: i32,
+) -> Instruction {
+    let (mock_price_account, _) = find_mock_price_address(program_id, authority);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(mock_price_account, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: EscrowInstruction::SetMockPrice { price, conf, expo }.pack(),
+    }
+}
+
+/// Creates an `InitializeMarketRegistry` instruction.
+pub fn initialize_market_registry(program_id: &Pubkey, upgrade_authority: &Pubkey) -> Instruction {
+    let (registry_account, _) = find_market_registry_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*upgrade_authority, true),
+            AccountMeta::new(registry_account, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(find_program_data_address(program_id).0, false),
+        ],
+        data: EscrowInstruction::InitializeMarketRegistry.pack(),
+    }
+}
+
+/// Creates an `AddMarket` instruction.
+pub fn add_market(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    oracle_account: &Pubkey,
+    symbol: [u8; 8],
+    oracle_type: OracleType,
+    max_join_drift_bps: u16,
+    win_threshold_bps: u16,
+) -> Instruction {
+    let (registry_account, _) = find_market_registry_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(registry_account, false),
+            AccountMeta::new_readonly(*oracle_account, false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        ],
+        data: EscrowInstruction::AddMarket {
+            symbol,
+            oracle_type,
+            max_join_drift_bps,
+            win_threshold_bps,
+        }
+        .pack(),
+    }
+}
+
+/// Creates an `UpdateMarket` instruction.
+pub fn update_market(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    market_id: u8,
+    max_join_drift_bps: u16,
+    win_threshold_bps: u16,
+    active: bool,
+) -> Instruction {
+    let (registry_account, _) = find_market_registry_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(registry_account, false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        ],
+        data: EscrowInstruction::UpdateMarket {
+            market_id,
+            max_join_drift_bps,
+            win_threshold_bps,
+            active,
+        }
+        .pack(),
+    }
+}
+
+/// Creates an `InitializeConfig` instruction.
+pub fn initialize_config(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    default_max_join_drift_bps: u16,
+    default_win_threshold_bps: u16,
+    fee_bps: u16,
+    treasury: &Pubkey,
+    timelock_delay: i64,
+) -> Instruction {
+    let (config_account, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(config_account, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(find_program_data_address(program_id).0, false),
+        ],
+        data: EscrowInstruction::InitializeConfig {
+            default_max_join_drift_bps,
+            default_win_threshold_bps,
+            fee_bps,
+            treasury: *treasury,
+            timelock_delay,
+        }
+        .pack(),
+    }
+}
+
+/// Creates an `UpdateConfig` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn update_config(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    allowed_mints: Vec<Pubkey>,
+    default_max_join_drift_bps: u16,
+    default_win_threshold_bps: u16,
+    fee_bps: u16,
+    treasury: &Pubkey,
+    timelock_delay: i64,
+) -> Instruction {
+    let (config_account, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(config_account, false),
+        ],
+        data: EscrowInstruction::UpdateConfig {
+            allowed_mints,
+            default_max_join_drift_bps,
+            default_win_threshold_bps,
+            fee_bps,
+            treasury: *treasury,
+            timelock_delay,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `SetPaused` instruction.
+pub fn set_paused(program_id: &Pubkey, admin: &Pubkey, paused: bool) -> Instruction {
+    let (config_account, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(config_account, false),
+        ],
+        data: EscrowInstruction::SetPaused { paused }.pack(),
+    }
+}
+
+/// Creates a `ProposeAdmin` instruction.
+pub fn propose_admin(program_id: &Pubkey, admin: &Pubkey, new_admin: &Pubkey) -> Instruction {
+    let (config_account, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(config_account, false),
+        ],
+        data: EscrowInstruction::ProposeAdmin { new_admin: *new_admin }.pack(),
+    }
+}
+
+/// Creates an `AcceptAdmin` instruction.
+pub fn accept_admin(program_id: &Pubkey, new_admin: &Pubkey) -> Instruction {
+    let (config_account, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*new_admin, true),
+            AccountMeta::new(config_account, false),
+        ],
+        data: EscrowInstruction::AcceptAdmin.pack(),
+    }
+}
+
+/// Creates an `ApplyConfigUpdate` instruction.
+pub fn apply_config_update(program_id: &Pubkey) -> Instruction {
+    let (config_account, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(config_account, false)],
+        data: EscrowInstruction::ApplyConfigUpdate.pack(),
+    }
+}
+
+/// Creates an `ApplyMarketUpdate` instruction.
+pub fn apply_market_update(program_id: &Pubkey) -> Instruction {
+    let (registry_account, _) = find_market_registry_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(registry_account, false)],
+        data: EscrowInstruction::ApplyMarketUpdate.pack(),
+    }
+}
+
+/// Creates a `CancelPendingUpdates` instruction.
+pub fn cancel_pending_updates(program_id: &Pubkey, admin: &Pubkey) -> Instruction {
+    let (config_account, _) = find_config_address(program_id);
+    let (registry_account, _) = find_market_registry_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(config_account, false),
+            AccountMeta::new(registry_account, false),
+        ],
+        data: EscrowInstruction::CancelPendingUpdates.pack(),
+    }
+}
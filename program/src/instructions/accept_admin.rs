@@ -0,0 +1,46 @@
+use crate::{
+    error::EscrowError,
+    validation::{assert_config, assert_signer},
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+/// Hands the config over to the proposed admin, who must sign to prove they hold the key
+pub fn accept_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+
+    msg!("Entering the accept_admin instruction");
+
+    let accounts_iter = &mut accounts.iter();
+
+    let new_admin = next_account_info(accounts_iter)?; // Proposed admin
+    let config_account = next_account_info(accounts_iter)?; // Config PDA
+
+    assert_signer(new_admin)?;
+    let mut config = assert_config(config_account, program_id)?;
+    if config.pending_admin == Pubkey::default() {
+        msg!("Impossible to accept: no admin has been proposed.");
+        return Err(EscrowError::NothingPending.into());
+    }
+    if *new_admin.key != config.pending_admin {
+        msg!("Impossible to accept: signer is not the proposed admin {}.", config.pending_admin);
+        return Err(EscrowError::Unauthorized.into());
+    }
+
+    let previous_admin = config.admin;
+    config.admin = config.pending_admin;
+    config.pending_admin = Pubkey::default();
+
+    let config_data = config.try_to_vec()?;
+    config_account
+        .try_borrow_mut_data()?
+        .copy_from_slice(&config_data);
+
+    msg!("Admin handed over from {} to {}", previous_admin, config.admin);
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use crate::{
+    error::EscrowError,
+    math::add_seconds,
+    oracle::OracleType,
+    state::market_registry::{Market, MAX_MARKETS},
+    validation::{assert_admin, assert_config, assert_market_parameters, assert_market_registry},
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+/// Queues a market on an approved price feed, registered by `apply_market_update`
+/// once the config's timelock has elapsed
+pub fn add_market(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    symbol: [u8; 8],
+    oracle_type: OracleType,
+    max_join_drift_bps: u16,
+    win_threshold_bps: u16,
+) -> ProgramResult {
+
+    msg!("Entering the add_market instruction");
+
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?; // Program admin
+    let registry_account = next_account_info(accounts_iter)?; // Market registry PDA
+    let oracle_account = next_account_info(accounts_iter)?; // Price feed approved for the market
+    let config_account = next_account_info(accounts_iter)?; // Program config holding the default thresholds
+
+    let config = assert_config(config_account, program_id)?;
+    assert_admin(admin, &config)?;
+    let mut registry = assert_market_registry(registry_account, program_id)?;
+
+    if registry.market_count as usize >= MAX_MARKETS {
+        msg!("Impossible to add market: the registry already holds {} markets.", MAX_MARKETS);
+        return Err(EscrowError::MarketRegistryFull.into());
+    }
+
+    // Markets registered without thresholds take the config's defaults
+    let max_join_drift_bps = match max_join_drift_bps {
+        0 => config.settings.default_max_join_drift_bps,
+        bps => bps,
+    };
+    let win_threshold_bps = match win_threshold_bps {
+        0 => config.settings.default_win_threshold_bps,
+        bps => bps,
+    };
+    assert_market_parameters(max_join_drift_bps, win_threshold_bps)?;
+
+    // Games pin the market's feed, so it must be one the program can read
+    oracle_type.source().validate(program_id, oracle_account)?;
+
+    let market_id = registry.market_count;
+    let market = Market {
+        symbol,
+        oracle: *oracle_account.key,
+        oracle_type,
+        max_join_drift_bps,
+        win_threshold_bps,
+        active: true,
+    };
+    let effective_at = add_seconds(Clock::get()?.unix_timestamp, config.settings.timelock_delay)?;
+    registry.queue_market(market_id, market, effective_at);
+
+    let registry_data = registry.try_to_vec()?;
+    registry_account
+        .try_borrow_mut_data()?
+        .copy_from_slice(&registry_data);
+
+    msg!(
+        "Market {} queued with id {} and feed {} until {}",
+        market.symbol(),
+        market_id,
+        oracle_account.key,
+        effective_at
+    );
+
+    Ok(())
+}
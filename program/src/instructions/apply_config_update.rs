@@ -0,0 +1,47 @@
+use crate::{
+    error::EscrowError,
+    validation::{assert_config, assert_timelock_elapsed},
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+/// Puts the queued settings in force once their timelock has elapsed. Anyone can
+/// send it; games already created keep the fee and thresholds they started with.
+pub fn apply_config_update(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+
+    msg!("Entering the apply_config_update instruction");
+
+    let accounts_iter = &mut accounts.iter();
+
+    let config_account = next_account_info(accounts_iter)?; // Config PDA
+
+    let mut config = assert_config(config_account, program_id)?;
+    if !config.has_pending_settings {
+        msg!("Impossible to apply: no config update is queued.");
+        return Err(EscrowError::NothingPending.into());
+    }
+    assert_timelock_elapsed(config.pending_settings_at)?;
+
+    config.settings = config.pending_settings;
+    config.clear_pending_settings();
+
+    let config_data = config.try_to_vec()?;
+    config_account
+        .try_borrow_mut_data()?
+        .copy_from_slice(&config_data);
+
+    msg!(
+        "Config update applied: {} allowed mints, fee {} bps to {}, timelock {} seconds",
+        config.settings.allowed_mint_count,
+        config.settings.fee_bps,
+        config.settings.treasury,
+        config.settings.timelock_delay
+    );
+
+    Ok(())
+}
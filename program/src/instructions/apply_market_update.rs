@@ -0,0 +1,62 @@
+use crate::{
+    error::EscrowError,
+    state::market_registry::MAX_MARKETS,
+    validation::{assert_market_registry, assert_timelock_elapsed},
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+/// Registers or updates the queued market once its timelock has elapsed. Anyone
+/// can send it; games already created keep the feed and thresholds they started with.
+pub fn apply_market_update(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+
+    msg!("Entering the apply_market_update instruction");
+
+    let accounts_iter = &mut accounts.iter();
+
+    let registry_account = next_account_info(accounts_iter)?; // Market registry PDA
+
+    let mut registry = assert_market_registry(registry_account, program_id)?;
+    if !registry.has_pending_market {
+        msg!("Impossible to apply: no market change is queued.");
+        return Err(EscrowError::NothingPending.into());
+    }
+    assert_timelock_elapsed(registry.pending_market_at)?;
+
+    // A change queued for the id after the last market registers a new one
+    let market_id = registry.pending_market_id;
+    if market_id == registry.market_count {
+        if market_id as usize >= MAX_MARKETS {
+            msg!("Impossible to add market: the registry already holds {} markets.", MAX_MARKETS);
+            return Err(EscrowError::MarketRegistryFull.into());
+        }
+        registry.market_count += 1;
+    } else {
+        registry.market(market_id)?;
+    }
+    registry.markets[market_id as usize] = registry.pending_market;
+    registry.clear_pending_market();
+
+    let market = &registry.markets[market_id as usize];
+    msg!(
+        "Market {} applied with id {}: feed {}, join drift {} bps, win threshold {} bps, active {}",
+        market.symbol(),
+        market_id,
+        market.oracle,
+        market.max_join_drift_bps,
+        market.win_threshold_bps,
+        market.active
+    );
+
+    let registry_data = registry.try_to_vec()?;
+    registry_account
+        .try_borrow_mut_data()?
+        .copy_from_slice(&registry_data);
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use crate::{
+    error::EscrowError,
+    validation::{assert_admin, assert_config, assert_market_registry},
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+/// Drops the queued config update and market change before they can be applied
+pub fn cancel_pending_updates(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+
+    msg!("Entering the cancel_pending_updates instruction");
+
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?; // Program admin
+    let config_account = next_account_info(accounts_iter)?; // Config PDA
+    let registry_account = next_account_info(accounts_iter)?; // Market registry PDA
+
+    let mut config = assert_config(config_account, program_id)?;
+    assert_admin(admin, &config)?;
+    let mut registry = assert_market_registry(registry_account, program_id)?;
+
+    if !config.has_pending_settings && !registry.has_pending_market {
+        msg!("Impossible to cancel: nothing is queued.");
+        return Err(EscrowError::NothingPending.into());
+    }
+
+    config.clear_pending_settings();
+    registry.clear_pending_market();
+
+    let config_data = config.try_to_vec()?;
+    config_account
+        .try_borrow_mut_data()?
+        .copy_from_slice(&config_data);
+    let registry_data = registry.try_to_vec()?;
+    registry_account
+        .try_borrow_mut_data()?
+        .copy_from_slice(&registry_data);
+
+    msg!("Queued config and market updates cancelled");
+
+    Ok(())
+}
@@ -1,18 +1,28 @@
-use crate::{state::game_state::GameState};
-use borsh::{BorshDeserialize};
-use solana_program::program_pack::Pack;
+use crate::{
+    error::EscrowError,
+    math::split_payout,
+    pda::VAULT_SEED,
+    state::{
+        game_state::{close_game_account, GameState},
+        game_status::GameStatus,
+    },
+    token::{close_vault, transfer, unpack_token_account},
+    validation::{
+        assert_escrow_token_account, assert_game_address, assert_mint, assert_not_paused,
+        assert_owned_by, assert_rent_recipient, assert_token_mint, assert_token_owner,
+        assert_token_program, assert_vault_authority,
+    },
+};
+use borsh::BorshDeserialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
-    program::invoke,
-    program_error::ProgramError,
     pubkey::Pubkey,
 };
-use spl_token::instruction::transfer as spl_transfer;
-use spl_token::state::Account as TokenAccount;
 
 pub fn close_game(
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     
@@ -20,70 +30,97 @@ pub fn close_game(
     let accounts_iter = &mut accounts.iter();
 
     let escrow_account = next_account_info(accounts_iter)?; // Escrow account holding the game state
-    let escrow_token_account_authority = next_account_info(accounts_iter)?;
+    let vault_authority = next_account_info(accounts_iter)?; // PDA owning the escrow token account
     let escrow_token_account = next_account_info(accounts_iter)?; // Escrow token account holding USDC
-    let fund_token_account_player1 = next_account_info(accounts_iter)?; // Player 1's USDC token account
-    let fund_token_account_player2 = next_account_info(accounts_iter)?; // Player 2's USDC token account
-    let token_program = next_account_info(accounts_iter)?; // SPL token program
-
-    // Deserialize the current game state from the escrow account
-    let game_state = GameState::try_from_slice(&escrow_account.try_borrow_data()?)?;
+    let mint = next_account_info(accounts_iter)?; // Mint of the staked token
+    let winner_token_account = next_account_info(accounts_iter)?; // Winner's USDC token account
+    let token_program = next_account_info(accounts_iter)?; // SPL Token or Token-2022 program owning the mint
+    let config_account = next_account_info(accounts_iter)?; // Program config
+    let treasury_token_account = next_account_info(accounts_iter)?; // Treasury's token account receiving the fee
+    let player1 = next_account_info(accounts_iter)?; // Player 1, who paid the game account's rent
 
-    // Ensure the game is  inactive
-    if game_state.game_active {
-        msg!("Impossible to close game, game is still active");
-        return Err(ProgramError::InvalidAccountData);
-    }
+    // Payouts wait while the program is paused, in case the game was settled on a bad price
+    let config = assert_not_paused(config_account, program_id)?;
 
-    // Ensure that there is a winner
-    if game_state.winner == Pubkey::default() {
-        msg!("Impossible to close game, there is no winner");
-        return Err(ProgramError::InvalidAccountData);
-    }
+    assert_owned_by(escrow_account, program_id)?;
 
-    // Logic to check the winner and assign the correct token account
-    let token_account_data_player1 = TokenAccount::unpack(&fund_token_account_player1.try_borrow_data()?)?;
-    let authority_player1 = token_account_data_player1.owner;
+    // Deserialize the current game state from the escrow account
+    let mut game_state = GameState::try_from_slice(&escrow_account.try_borrow_data()?)?;
+    assert_game_address(escrow_account, program_id, &game_state)?;
 
-    let token_account_data_player2 = TokenAccount::unpack(&fund_token_account_player2.try_borrow_data()?)?;
-    let authority_player2 = token_account_data_player2.owner;
+    // Only a settled game has a winner to pay, and only once
+    game_state.status.transition_to(GameStatus::PaidOut)?;
 
-    let winner_token_account: &AccountInfo;
+    assert_escrow_token_account(escrow_token_account, &game_state.escrow_token_account)?;
+    assert_vault_authority(vault_authority, program_id, escrow_account.key, game_state.vault_bump)?;
+    let decimals = assert_mint(mint, &game_state.mint)?.decimals;
+    assert_token_program(token_program, mint)?;
+    assert_token_mint(winner_token_account, &game_state.mint)?;
+    assert_token_mint(treasury_token_account, &game_state.mint)?;
+    assert_rent_recipient(player1, &game_state)?;
 
-    // Check if the authority matches the winner
-    if authority_player1 == game_state.winner {
-        winner_token_account = fund_token_account_player1;
+    // The payout can only go to a token account owned by the winner
+    assert_token_owner(winner_token_account, &game_state.winner)
+        .map_err(|_| EscrowError::WinnerAccountMismatch)?;
+    if game_state.winner == game_state.player1 {
         msg!("Winner is Player 1");
-    } else if authority_player2 == game_state.winner {
-        winner_token_account = fund_token_account_player2;
-        msg!("Winner is Player 2");
     } else {
-        msg!("No valid winner found");
-        return Err(ProgramError::InvalidAccountData);
+        msg!("Winner is Player 2");
     }
 
+    // The protocol fee can only go to the treasury recorded in the config
+    assert_token_owner(treasury_token_account, &config.settings.treasury)
+        .map_err(|_| EscrowError::TreasuryMismatch)?;
+
+    // Everything in the vault (both stakes) is paid out, the fee agreed at creation to the
+    // treasury and the rest to the winner
+    let usdc_amount = unpack_token_account(escrow_token_account)?.amount;
+    let (winner_amount, fee) = split_payout(usdc_amount, game_state.fee_bps)?;
 
-    // Transfer 2000 USDC (2,000 micro USDC) from the escrow token account to the winner's token account
-    let usdc_amount: u64 = 2000_000_000; // 2000 USDC in micro units
+    // The vault authority PDA signs for the escrow token account
+    let vault_seeds: &[&[u8]] = &[VAULT_SEED, escrow_account.key.as_ref(), &[game_state.vault_bump]];
+    let received = transfer(
+        token_program,
+        escrow_token_account,
+        mint,
+        winner_token_account,
+        vault_authority,
+        winner_amount,
+        decimals,
+        &[vault_seeds],
+    )?;
+    if fee > 0 {
+        transfer(
+            token_program,
+            escrow_token_account,
+            mint,
+            treasury_token_account,
+            vault_authority,
+            fee,
+            decimals,
+            &[vault_seeds],
+        )?;
+    }
 
-    // Ensure the escrow_token_account has the correct authority and ownership for SPL transfers
-    invoke(
-        &spl_transfer(
-            token_program.key,                  // SPL token program
-            escrow_token_account.key,           // Source account (escrow token account with USDC)
-            winner_token_account.key,           // Destination account (winner's USDC token account)
-            escrow_token_account_authority.key, // Authority (payer’s account)
-            &[],                                // No additional signers
-            usdc_amount,                        // Amount of USDC to transfer
-        )?,
-        &[
-            escrow_token_account_authority.clone(),
-            escrow_token_account.clone(),
-            winner_token_account.clone(),
-            token_program.clone(),
-        ],
+    // The game is over, so its vault and account go and their rent goes back to Player 1;
+    // any tokens left in the vault go to the winner
+    close_vault(
+        token_program,
+        escrow_token_account,
+        mint,
+        winner_token_account,
+        player1,
+        vault_authority,
+        decimals,
+        &[vault_seeds],
     )?;
+    close_game_account(escrow_account, player1)?;
 
-    msg!("Game closed successfully. Winner has been paid.");
+    msg!(
+        "Game closed successfully. Winner has been paid {} of {} held in escrow, fee {}.",
+        received,
+        usdc_amount,
+        fee
+    );
     Ok(())
 }
@@ -1,21 +1,45 @@
-use crate::{instructions::fetch_price::fetch_price, state::game_state::GameState};
+use crate::{
+    error::EscrowError,
+    instructions::fetch_price::fetch_price,
+    math::add_seconds,
+    pda::{find_game_address, find_vault_authority, get_vault_address, GAME_SEED},
+    price::{price_override, PRICE_EXPO},
+    state::{game_state::GameState, game_status::GameStatus},
+    token::{transfer, unpack_mint},
+    validation::{
+        assert_game_thresholds, assert_market_registry, assert_not_paused, assert_signer,
+        assert_token_mint, assert_token_owner, assert_token_program,
+    },
+};
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
-    program::invoke,
+    clock::Clock,
+    program::{invoke, invoke_signed},
     pubkey::Pubkey,
     system_instruction,
     sysvar::rent::Rent,
     sysvar::Sysvar,
 };
-use spl_token::instruction::transfer as spl_transfer;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
 
+#[allow(clippy::too_many_arguments)]
 pub fn create_game(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    instruction_data: &[u8],
+    player1_choice: bool,
+    entry_price_got: u64,
+    nonce: u64,
+    stake: u64,
+    duration: i64,
+    join_window: i64,
+    max_price_age: u64,
+    max_confidence_bps: u16,
+    market_id: u8,
+    max_join_drift_bps: u16,
+    win_threshold_bps: u16,
 ) -> ProgramResult {
 
     msg!("Entering the create_game instruction");
@@ -23,65 +47,224 @@ pub fn create_game(
     let accounts_iter = &mut accounts.iter();
 
     let payer = next_account_info(accounts_iter)?; // Player 1 (payer)
-    let escrow_account = next_account_info(accounts_iter)?; // Escrow account for game state
-    let escrow_token_account = next_account_info(accounts_iter)?; // Escrow USDC token account
+    let escrow_account = next_account_info(accounts_iter)?; // Escrow account for game state, PDA of (player 1, nonce)
+    let escrow_token_account = next_account_info(accounts_iter)?; // Escrow USDC token account (vault), created here unless it already exists
+    let vault_authority = next_account_info(accounts_iter)?; // PDA owning the vault
+    let mint = next_account_info(accounts_iter)?; // Mint of the staked token
     let payer_token_account = next_account_info(accounts_iter)?; // Payer's USDC token account
-    let token_program = next_account_info(accounts_iter)?; // Token program for SPL tokens
-    let oracle_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?; // SPL Token or Token-2022 program owning the mint
+    let associated_token_program = next_account_info(accounts_iter)?; // Associated token account program
+    let oracle_account = next_account_info(accounts_iter)?; // Price feed pinned for the whole game
     let system_program = next_account_info(accounts_iter)?; // System program
+    let registry_account = next_account_info(accounts_iter)?; // Market registry holding the game's market
+    let config_account = next_account_info(accounts_iter)?; // Program config
+
+    // Player 1 must authorise the stake transfer and pay for the escrow account
+    assert_signer(payer)?;
+    let config = assert_not_paused(config_account, program_id)?;
+
+    // The game account is addressed by Player 1 and the nonce, so no extra keypair is needed
+    let (expected_game_account, bump) = find_game_address(program_id, payer.key, nonce);
+    if *escrow_account.key != expected_game_account {
+        msg!("Escrow account is not the PDA derived from Player 1 and the nonce");
+        return Err(EscrowError::InvalidGameAccount.into());
+    }
+
+    // The vault must be the PDA-owned associated token account of this game
+    let (expected_vault_authority, vault_bump) = find_vault_authority(program_id, escrow_account.key);
+    if *vault_authority.key != expected_vault_authority {
+        msg!("Vault authority is not the PDA derived from the game account");
+        return Err(EscrowError::InvalidVaultAuthority.into());
+    }
+    if *escrow_token_account.key
+        != get_vault_address(program_id, escrow_account.key, mint.key, token_program.key)
+    {
+        return Err(EscrowError::EscrowTokenAccountMismatch.into());
+    }
+
+    if stake == 0 {
+        msg!("Impossible to create game, the stake must be greater than zero");
+        return Err(EscrowError::InvalidStake.into());
+    }
+
+    // Without a winning move the game can only end by expiring, so it needs a finite duration
+    if duration <= 0 {
+        msg!("Impossible to create game, the duration must be greater than zero");
+        return Err(EscrowError::InvalidDuration.into());
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let expires_at = add_seconds(now, duration)?;
+
+    // Player 2 must join while the entry price is still fresh, and before the game expires
+    if join_window <= 0 || join_window > duration {
+        msg!("Impossible to create game, the join window must be between 1 and {} seconds", duration);
+        return Err(EscrowError::InvalidJoinWindow.into());
+    }
+    let join_deadline = add_seconds(now, join_window)?;
+
+    // Every oracle read is checked against these, so they must allow some price through
+    if max_price_age == 0
+        || max_price_age > i64::MAX as u64
+        || max_confidence_bps == 0
+        || max_confidence_bps > 10_000
+    {
+        msg!("Impossible to create game, the maximum price age and confidence must be positive");
+        return Err(EscrowError::InvalidOracleParameters.into());
+    }
 
-    let usdc_amount: u64 = 1000_000_000; // USDC Token created has 6 decimals, so 1000 USDC is represented as 1,000,000,000 in smallest units
+    // Games are played on a registered market, priced by the feed approved for it
+    let registry = assert_market_registry(registry_account, program_id)?;
+    let market = registry.market(market_id)?;
+    if !market.active {
+        msg!("Impossible to create game, market {} is not active", market.symbol());
+        return Err(EscrowError::InvalidMarket.into());
+    }
+    if *oracle_account.key != market.oracle {
+        msg!("Oracle account {} is not the feed approved for market {}", oracle_account.key, market.symbol());
+        return Err(EscrowError::OracleMismatch.into());
+    }
+    let oracle_type = market.oracle_type;
+
+    // Player 1 can tighten or loosen the market's defaults, within the program-wide bounds
+    let max_join_drift_bps = match max_join_drift_bps {
+        0 => market.max_join_drift_bps,
+        bps => bps,
+    };
+    let win_threshold_bps = match win_threshold_bps {
+        0 => market.win_threshold_bps,
+        bps => bps,
+    };
+    assert_game_thresholds(max_join_drift_bps, win_threshold_bps)?;
 
-    let player1_choice = instruction_data[1] != 0; // If the second byte is 1, player1_choice is true, otherwise it's false
+    // The feed is pinned for the game's lifetime, so it must be a feed of the market's kind
+    oracle_type.source().validate(program_id, oracle_account)?;
 
-    let entry_price_got = u64::from_le_bytes(instruction_data[2..10].try_into().unwrap());
-    let last_price = entry_price_got;
     msg!("entry_price_got {:?}", entry_price_got);
+    let entry_price_override = price_override(entry_price_got)?;
 
-    // Transfer 1000 USDC from payer's token account to the escrow token account
-    invoke(
-        &spl_transfer(
-            token_program.key,         // SPL token program ID
-            payer_token_account.key,   // Source token account
-            escrow_token_account.key,  // Destination token account
-            payer.key,                 // Authority (signer)
-            &[],                       // No multisig signers
-            usdc_amount,               // Amount
-        )?,
-        &[
-            payer_token_account.clone(),   // 0. Source token account
-            escrow_token_account.clone(),  // 1. Destination token account
-            payer.clone(),                 // 2. Authority
-            token_program.clone(),         // 3. Token program
-        ],
-    )?;
+    // Any SPL Token or Token-2022 mint the config allows can be staked; amounts are in its smallest units
+    if !config.settings.is_mint_allowed(mint.key) {
+        msg!("Impossible to create game, mint {} is not allowed", mint.key);
+        return Err(EscrowError::MintNotAllowed.into());
+    }
+    assert_token_program(token_program, mint)?;
+    let decimals = unpack_mint(mint)?.decimals;
+    assert_token_mint(payer_token_account, mint.key)?;
 
-    // Create the escrow account (for holding the game state)
-    let rent = Rent::get()?;
-    let game_state_size = GameState::default().try_to_vec()?.len(); // Size of the serialized game state
-    let required_lamports_for_escrow = rent.minimum_balance(game_state_size);
+    // Create the vault as the associated token account of the vault authority PDA,
+    // so only this program can sign transfers out of it. Its address is public, so
+    // anyone may have created it first; that must not block the game
     invoke(
-        &system_instruction::create_account(
+        &create_associated_token_account_idempotent(
             payer.key,
-            escrow_account.key,           // Create the escrow account
-            required_lamports_for_escrow, // Rent exemption for holding the game state
-            game_state_size as u64,       // Size of the game state
-            program_id,                   // The program that owns this account (your program)
+            vault_authority.key,
+            mint.key,
+            token_program.key,
         ),
         &[
             payer.clone(),
-            escrow_account.clone(),
+            escrow_token_account.clone(),
+            vault_authority.clone(),
+            mint.clone(),
             system_program.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
         ],
     )?;
+    assert_token_owner(escrow_token_account, vault_authority.key)?;
+    assert_token_mint(escrow_token_account, mint.key)?;
 
-    // Initialize the game state and store it in the escrow account
-    let mut game_state = GameState::default();
-    game_state.player1 = *payer.key;
-    game_state.game_active = true;
+    // Transfer Player 1's stake from payer's token account to the escrow token account;
+    // a transfer-fee mint withholds part of it, so record what actually landed
+    let deposited = transfer(
+        token_program,
+        payer_token_account,
+        mint,
+        escrow_token_account,
+        payer,
+        stake,
+        decimals,
+        &[],
+    )?;
+    msg!("Stake of {} deposited, {} landed in the vault", stake, deposited);
+
+    // Create the escrow account (for holding the game state)
+    let rent = Rent::get()?;
+    let game_state_size = GameState::default().try_to_vec()?.len(); // Size of the serialized game state
+    let required_lamports_for_escrow = rent.minimum_balance(game_state_size);
+    let nonce_bytes = nonce.to_le_bytes();
+    let game_seeds: &[&[u8]] = &[GAME_SEED, payer.key.as_ref(), &nonce_bytes, &[bump]];
+    let escrow_lamports = escrow_account.lamports();
+    if escrow_lamports == 0 {
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                escrow_account.key,           // Create the escrow account
+                required_lamports_for_escrow, // Rent exemption for holding the game state
+                game_state_size as u64,       // Size of the game state
+                program_id,                   // The program that owns this account (your program)
+            ),
+            &[
+                payer.clone(),
+                escrow_account.clone(),
+                system_program.clone(),
+            ],
+            &[game_seeds],
+        )?;
+    } else {
+        // create_account refuses an address that already holds lamports, and anyone can send
+        // some to this one; like an Anchor `init`, top it up to rent exemption, then allocate
+        // and assign it. Allocating still fails if the game already exists
+        let shortfall = required_lamports_for_escrow.saturating_sub(escrow_lamports);
+        if shortfall > 0 {
+            invoke(
+                &system_instruction::transfer(payer.key, escrow_account.key, shortfall),
+                &[
+                    payer.clone(),
+                    escrow_account.clone(),
+                    system_program.clone(),
+                ],
+            )?;
+        }
+        invoke_signed(
+            &system_instruction::allocate(escrow_account.key, game_state_size as u64),
+            &[escrow_account.clone(), system_program.clone()],
+            &[game_seeds],
+        )?;
+        invoke_signed(
+            &system_instruction::assign(escrow_account.key, program_id),
+            &[escrow_account.clone(), system_program.clone()],
+            &[game_seeds],
+        )?;
+    }
 
-    game_state.player1_choice = player1_choice;
-    game_state.player2_choice = !player1_choice;
+    // Initialize the game state and store it in the escrow account
+    let game_state = GameState {
+        player1: *payer.key,
+        status: GameStatus::Open,
+        player1_choice,
+        player2_choice: !player1_choice,
+        escrow_token_account: *escrow_token_account.key,
+        vault_bump,
+        nonce,
+        bump,
+        stake,
+        mint: *mint.key,
+        deposited,
+        expires_at,
+        join_deadline,
+        max_price_age,
+        max_confidence_bps,
+        price_expo: PRICE_EXPO,
+        oracle: *oracle_account.key,
+        oracle_program: oracle_type.source().owner(program_id),
+        oracle_type,
+        market_id,
+        max_join_drift_bps,
+        win_threshold_bps,
+        fee_bps: config.settings.fee_bps,
+        ..GameState::default()
+    };
 
     // Serialize the game state and store it in the escrow account
     let game_state_data = game_state.try_to_vec()?; // Convert GameState to a byte vector
@@ -89,17 +272,17 @@ pub fn create_game(
         .try_borrow_mut_data()?
         .copy_from_slice(&game_state_data); // Write to escrow account
 
-    msg!("Game created successfully with escrow and token accounts.");
+    msg!("Game created on market {} with escrow and token accounts.", market.symbol());
 
     let mut updated_game_state = GameState::try_from_slice(&escrow_account.try_borrow_data()?)?;
-    // Check if `last_price_got` is invalid (e.g., zero or a placeholder value), then fetch from the oracle
-    if entry_price_got == 0 || instruction_data.len() < 10 {
-        msg!("entry_price_got is invalid, fetching price from the oracle");
+    // Prices come from the oracle unless a test build was given an override
+    if entry_price_override.is_none() {
+        msg!("No price override, fetching price from the oracle");
 
         // Fetch the price from the oracle and update game state (stored in escrow account)
         fetch_price(
             program_id,
-            &[oracle_account.clone(), escrow_account.clone()],
+            &[oracle_account.clone(), escrow_account.clone(), config_account.clone()],
         )?;
 
         // Re-fetch the updated game state from the escrow account
@@ -113,8 +296,8 @@ pub fn create_game(
             updated_game_state.last_price
         );
     } else {
-        updated_game_state.entry_price = entry_price_got; // Set the entry price as 1000 USDC
-        updated_game_state.last_price = last_price;
+        updated_game_state.entry_price = entry_price_got;
+        updated_game_state.last_price = entry_price_got;
         msg!("Using manually provided entry_price: {}", entry_price_got);
     }
 
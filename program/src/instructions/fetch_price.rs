@@ -1,48 +1,82 @@
-use crate::state::game_state::GameState;
+use crate::{
+    error::EscrowError,
+    math::exceeds_bps,
+    price::normalize_price,
+    state::game_state::GameState,
+    validation::{assert_game_address, assert_not_paused, assert_oracle, assert_owned_by},
+};
 use borsh::{BorshDeserialize, BorshSerialize};
-use pyth_sdk_solana::{load_price_feed_from_account_info, PriceFeed, PythError};
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
     pubkey::Pubkey,
+    sysvar::Sysvar,
 };
 
-/// Function to fetch the ETH/USDC price from a Pyth oracle account
-pub fn fetch_price(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+/// Function to fetch the market price from the game's oracle account
+pub fn fetch_price(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
 
     msg!("Entering the fetch_price instruction");
 
-    // Assuming the oracle account is the first in the list of accounts passed
-    let oracle_account = &accounts[0];
-    let escrow_account = &accounts[1]; // Escrow account for game state
+    let accounts_iter = &mut accounts.iter();
+
+    let oracle_account = next_account_info(accounts_iter)?; // Oracle account pinned by the game
+    let escrow_account = next_account_info(accounts_iter)?; // Escrow account for game state
+    let config_account = next_account_info(accounts_iter)?; // Program config
+
+    assert_not_paused(config_account, program_id)?;
 
     msg!("Oracle account pubkey: {}", oracle_account.key);
     msg!("Escrow account pubkey: {}", escrow_account.key);
 
-    // Load the price feed from the oracle account
-    msg!("Loading price feed from oracle account...");
-    let price_feed_result: Result<PriceFeed, PythError> =
-        load_price_feed_from_account_info(oracle_account);
+    // Deserialize the current game state from the escrow account
+    msg!("Deserializing current game state from escrow account...");
+    assert_owned_by(escrow_account, program_id)?;
+    let mut game_state = GameState::try_from_slice(&escrow_account.try_borrow_data()?)?;
+    assert_game_address(escrow_account, program_id, &game_state)?;
+
+    // A decided or refunded game keeps the last price it was played at
+    if !game_state.status.is_in_play() {
+        msg!("Impossible to fetch price, game is {:?}", game_state.status);
+        return Err(EscrowError::GameInactive.into());
+    }
 
-    // Handle the result of loading the price feed
-    let price_feed = match price_feed_result {
-        Ok(feed) => feed,
-        Err(_) => return Err(ProgramError::InvalidAccountData), // Return error if loading the price feed fails
-    };
+    // Only the feed pinned at creation can price this game
+    assert_oracle(oracle_account, &game_state)?;
 
-    // Fetch the current price from the price feed
-    msg!("Fetching current ETH/USDC price from price feed...");
-    let price = price_feed
-        .get_current_price()
-        .ok_or(ProgramError::InvalidAccountData)?;
+    // Fetch the current price, no older than the game allows
+    msg!("Fetching current price of market {} from price feed...", game_state.market_id);
+    let price = game_state
+        .oracle_type
+        .source()
+        .read_price(program_id, oracle_account)?;
+    let current_time = Clock::get()?.unix_timestamp;
+    if current_time.saturating_sub(price.publish_time) > game_state.max_price_age as i64 {
+        msg!(
+            "Oracle price published at {} is older than {} seconds",
+            price.publish_time,
+            game_state.max_price_age
+        );
+        return Err(EscrowError::StalePrice.into());
+    }
 
-    msg!("Price of ETH/USDC: {}", price.price);
+    msg!("Price: {} +/- {} (expo {})", price.price, price.conf, price.expo);
 
-    // Deserialize the current game state from the escrow account
-    msg!("Deserializing current game state from escrow account...");
-    let mut game_state = GameState::try_from_slice(&escrow_account.try_borrow_data()?)?;
+    // Reject prices whose confidence interval is too wide relative to the price
+    if exceeds_bps(price.conf, price.price.unsigned_abs(), game_state.max_confidence_bps) {
+        msg!(
+            "Oracle confidence {} exceeds {} bps of the price {}",
+            price.conf,
+            game_state.max_confidence_bps,
+            price.price
+        );
+        return Err(EscrowError::PriceTooUncertain.into());
+    }
 
-    //Updating the last price with the price obtained from the oracle
-    game_state.last_price = price.price as u64;
+    // Store the price with the game's exponent so it compares with prices from any feed
+    game_state.last_price = normalize_price(price.price, price.expo)?;
 
     // Serialize and store the updated game state back into the escrow account
     msg!("Serializing updated game state and storing in escrow account...");
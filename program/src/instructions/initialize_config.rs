@@ -0,0 +1,94 @@
+use crate::{
+    error::EscrowError,
+    pda::{find_config_address, CONFIG_SEED},
+    state::config::{Config, ConfigSettings},
+    validation::{assert_config_settings, assert_upgrade_authority},
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+pub fn initialize_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    default_max_join_drift_bps: u16,
+    default_win_threshold_bps: u16,
+    fee_bps: u16,
+    treasury: Pubkey,
+    timelock_delay: i64,
+) -> ProgramResult {
+
+    msg!("Entering the initialize_config instruction");
+
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?; // Program upgrade authority, becomes the admin and pays for the config
+    let config_account = next_account_info(accounts_iter)?; // Config PDA, created here
+    let system_program = next_account_info(accounts_iter)?; // System program
+    let program_data = next_account_info(accounts_iter)?; // ProgramData account naming the upgrade authority
+
+    // Only the deployer can claim the admin role; it can hand it over afterwards
+    assert_upgrade_authority(admin, program_data, program_id)?;
+
+    let (expected_config_account, bump) = find_config_address(program_id);
+    if *config_account.key != expected_config_account {
+        msg!("Config is not the PDA derived from the program");
+        return Err(EscrowError::InvalidConfig.into());
+    }
+
+    // Any mint is allowed until the admin restricts them
+    let settings = ConfigSettings {
+        default_max_join_drift_bps,
+        default_win_threshold_bps,
+        fee_bps,
+        treasury,
+        timelock_delay,
+        ..ConfigSettings::default()
+    };
+    assert_config_settings(&settings)?;
+
+    let config = Config {
+        admin: *admin.key,
+        bump,
+        settings,
+        ..Config::default()
+    };
+    let config_data = config.try_to_vec()?;
+
+    // Creating the PDA fails if it already exists, so the config can only be initialised once
+    let rent = Rent::get()?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin.key,
+            config_account.key,
+            rent.minimum_balance(config_data.len()),
+            config_data.len() as u64,
+            program_id,
+        ),
+        &[
+            admin.clone(),
+            config_account.clone(),
+            system_program.clone(),
+        ],
+        &[&[CONFIG_SEED, &[bump]]],
+    )?;
+
+    config_account
+        .try_borrow_mut_data()?
+        .copy_from_slice(&config_data);
+
+    msg!(
+        "Config initialised with admin {} and a timelock of {} seconds",
+        admin.key,
+        timelock_delay
+    );
+
+    Ok(())
+}
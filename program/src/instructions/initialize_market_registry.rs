@@ -0,0 +1,72 @@
+use crate::{
+    error::EscrowError,
+    pda::{find_market_registry_address, MARKET_REGISTRY_SEED},
+    state::market_registry::MarketRegistry,
+    validation::assert_upgrade_authority,
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+pub fn initialize_market_registry(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    msg!("Entering the initialize_market_registry instruction");
+
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?; // Program upgrade authority, pays for the registry
+    let registry_account = next_account_info(accounts_iter)?; // Market registry PDA, created here
+    let system_program = next_account_info(accounts_iter)?; // System program
+    let program_data = next_account_info(accounts_iter)?; // ProgramData account naming the upgrade authority
+
+    // Only the deployer can create the registry; the config's admin manages it from then on
+    assert_upgrade_authority(admin, program_data, program_id)?;
+
+    let (expected_registry_account, bump) = find_market_registry_address(program_id);
+    if *registry_account.key != expected_registry_account {
+        msg!("Market registry is not the PDA derived from the program");
+        return Err(EscrowError::InvalidMarketRegistry.into());
+    }
+
+    let registry = MarketRegistry {
+        bump,
+        ..MarketRegistry::default()
+    };
+    let registry_data = registry.try_to_vec()?;
+
+    // Creating the PDA fails if it already exists, so the registry can only be initialised once
+    let rent = Rent::get()?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin.key,
+            registry_account.key,
+            rent.minimum_balance(registry_data.len()),
+            registry_data.len() as u64,
+            program_id,
+        ),
+        &[
+            admin.clone(),
+            registry_account.clone(),
+            system_program.clone(),
+        ],
+        &[&[MARKET_REGISTRY_SEED, &[bump]]],
+    )?;
+
+    registry_account
+        .try_borrow_mut_data()?
+        .copy_from_slice(&registry_data);
+
+    msg!("Market registry initialised by {}", admin.key);
+
+    Ok(())
+}
@@ -1,19 +1,30 @@
-use crate::{instructions::fetch_price::fetch_price, state::game_state::GameState};
+use crate::{
+    error::EscrowError,
+    instructions::fetch_price::fetch_price,
+    math::checked_add,
+    price::{assert_join_drift, price_override},
+    state::{game_state::GameState, game_status::GameStatus},
+    token::transfer,
+    validation::{
+        assert_escrow_token_account, assert_game_address, assert_mint, assert_not_paused,
+        assert_oracle, assert_owned_by, assert_signer, assert_token_mint, assert_token_program,
+    },
+};
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
     msg,
-    program::invoke,
-    program_error::ProgramError,
     pubkey::Pubkey,
+    sysvar::Sysvar,
 };
-use spl_token::instruction::transfer as spl_transfer;
 
 pub fn join_game(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    instruction_data: &[u8],
+    last_price_got: u64,
+    stake: u64,
 ) -> ProgramResult {
 
     msg!("Entering the join_game instruction");
@@ -23,30 +34,52 @@ pub fn join_game(
     let player2 = next_account_info(accounts_iter)?; // Player 2
     let escrow_account = next_account_info(accounts_iter)?; // Escrow account holding game state
     let escrow_token_account = next_account_info(accounts_iter)?; // Escrow token account (where Player 2's USDC will be deposited)
+    let mint = next_account_info(accounts_iter)?; // Mint of the staked token
     let player2_token_account = next_account_info(accounts_iter)?; // Player 2's USDC token account
-    let token_program = next_account_info(accounts_iter)?; // Token program account for SPL tokens
+    let token_program = next_account_info(accounts_iter)?; // SPL Token or Token-2022 program owning the mint
     let oracle_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?; // Program config
 
-    // Check if instruction_data contains last_price
-    let last_price_got = if instruction_data.len() >= 9 {
-        u64::from_le_bytes(instruction_data[1..9].try_into().unwrap())
-    } else {
-        0 // Default to 0 if last_price is not provided
-    };
+    // Nobody can join while the program is paused
+    assert_not_paused(config_account, program_id)?;
+
+    assert_signer(player2)?;
+    assert_owned_by(escrow_account, program_id)?;
 
     msg!("last_price_got {:?}", last_price_got);
 
     // Deserialize the current game state from the escrow account
     let mut game_state = GameState::try_from_slice(&escrow_account.try_borrow_data()?)?;
+    assert_game_address(escrow_account, program_id, &game_state)?;
+    assert_oracle(oracle_account, &game_state)?;
+    assert_escrow_token_account(escrow_token_account, &game_state.escrow_token_account)?;
+    let decimals = assert_mint(mint, &game_state.mint)?.decimals;
+    assert_token_program(token_program, mint)?;
+    assert_token_mint(player2_token_account, &game_state.mint)?;
+
+    // Player 2 must match Player 1's stake, checked before the price so a wrong stake
+    // is reported as such whatever the feed says
+    if stake != game_state.stake {
+        msg!("Impossible to join Player 2, stake {} does not match {}", stake, game_state.stake);
+        return Err(EscrowError::StakeMismatch.into());
+    }
 
-    // Check if `last_price_got` is invalid (e.g., zero or a placeholder value), then fetch from the oracle
-    if instruction_data.len() < 9 || last_price_got == 0 {
-        msg!("last_price_got is invalid, fetching price from the oracle");
+    // The entry price goes stale, so Player 2 can only join before the deadline
+    if Clock::get()?.unix_timestamp >= game_state.join_deadline {
+        msg!("Impossible to join Player 2, the join deadline {} has passed", game_state.join_deadline);
+        return Err(EscrowError::JoinDeadlinePassed.into());
+    }
+
+    let last_price_override = price_override(last_price_got)?;
+
+    // Prices come from the oracle unless a test build was given an override
+    if last_price_override.is_none() {
+        msg!("No price override, fetching price from the oracle");
 
         // Fetch the price from the oracle and update game state (stored in escrow account)
         fetch_price(
             program_id,
-            &[oracle_account.clone(), escrow_account.clone()],
+            &[oracle_account.clone(), escrow_account.clone(), config_account.clone()],
         )?;
 
         // Re-fetch the updated game state from the escrow account
@@ -59,27 +92,32 @@ pub fn join_game(
         msg!("Using manually provided last_price: {}", last_price_got);
     }
 
-    let fluctuation = game_state.last_price - game_state.entry_price;
-
-    let percentage = (fluctuation * 100) / game_state.entry_price;
-    msg!("Percentage {:?}", percentage);
-
-    if percentage > 1 {
-        msg!("Impossible to join Player 2, price fluctuation more than 1%.");
-        return Err(ProgramError::InvalidAccountData);
-    }
+    // The price may have moved either way since Player 1 entered
+    assert_join_drift(
+        game_state.entry_price,
+        game_state.last_price,
+        game_state.max_join_drift_bps,
+    )?;
 
-    // Ensure the game is still active
-    if !game_state.game_active {
-        return Err(ProgramError::InvalidAccountData);
-    }
+    // Player 2 can only join a game that is still waiting for them
+    game_state.status.transition_to(GameStatus::Matched)?;
+
+    // Transfer Player 2's stake from their token account to the escrow token account
+    let deposited = transfer(
+        token_program,
+        player2_token_account,
+        mint,
+        escrow_token_account,
+        player2,
+        game_state.stake,
+        decimals,
+        &[],
+    )?;
+    msg!("Stake of {} deposited, {} landed in the vault", game_state.stake, deposited);
 
-    // Ensure Player 2 is not already set
-    if game_state.player2 != Pubkey::default() {
-        return Err(ProgramError::InvalidAccountData);
-    }
     // Set Player 2 in the game state
     game_state.player2 = *player2.key;
+    game_state.deposited = checked_add(game_state.deposited, deposited)?;
 
     // Update and serialize the game state
     let game_state_data = game_state.try_to_vec()?; // Convert GameState to a byte vector
@@ -88,25 +126,6 @@ pub fn join_game(
         .try_borrow_mut_data()?
         .copy_from_slice(&game_state_data); // Store the serialized data into the account's data
 
-    // Transfer 1000 USDC (1,000,000,000 micro USDC) from Player 2's token account to the escrow token account
-    let usdc_amount: u64 = 1_000_000_000; // 1000 USDC in micro units
-    invoke(
-        &spl_transfer(
-            token_program.key,         // SPL token program
-            player2_token_account.key, // Source account (Player 2's USDC token account)
-            escrow_token_account.key,  // Destination account (escrow token account)
-            player2.key,               // Authority (Player 2's account)
-            &[],                       // No additional signers
-            usdc_amount,               // Amount of USDC to transfer
-        )?,
-        &[
-            player2.clone(),
-            player2_token_account.clone(),
-            escrow_token_account.clone(),
-            token_program.clone(),
-        ],
-    )?;
-
     msg!("Player 2 joined the game successfully.");
 
     Ok(())
@@ -4,3 +4,18 @@ pub mod settle_game;
 pub mod fetch_price;
 pub mod withdraw_funds;
 pub mod close_game;
+pub mod refund_draw;
+pub mod refund_unjoined;
+pub mod initialize_market_registry;
+pub mod add_market;
+pub mod update_market;
+pub mod initialize_config;
+pub mod update_config;
+pub mod set_paused;
+pub mod propose_admin;
+pub mod accept_admin;
+pub mod apply_config_update;
+pub mod apply_market_update;
+pub mod cancel_pending_updates;
+#[cfg(feature = "mock-oracle")]
+pub mod set_mock_price;
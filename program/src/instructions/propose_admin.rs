@@ -0,0 +1,37 @@
+use crate::validation::{assert_admin, assert_config};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+/// Proposes the next admin, who only takes over once they accept with `accept_admin`
+pub fn propose_admin(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_admin: Pubkey,
+) -> ProgramResult {
+
+    msg!("Entering the propose_admin instruction");
+
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?; // Program admin
+    let config_account = next_account_info(accounts_iter)?; // Config PDA
+
+    let mut config = assert_config(config_account, program_id)?;
+    assert_admin(admin, &config)?;
+
+    // Proposing the default key withdraws the current proposal
+    config.pending_admin = new_admin;
+    let config_data = config.try_to_vec()?;
+    config_account
+        .try_borrow_mut_data()?
+        .copy_from_slice(&config_data);
+
+    msg!("Admin {} proposed {} as the next admin", admin.key, new_admin);
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use crate::{
+    error::EscrowError,
+    math::split_draw,
+    pda::VAULT_SEED,
+    state::{
+        game_state::{close_game_account, GameState},
+        game_status::GameStatus,
+    },
+    token::{close_vault, transfer},
+    validation::{
+        assert_escrow_token_account, assert_game_address, assert_mint, assert_owned_by,
+        assert_rent_recipient, assert_token_mint, assert_token_owner, assert_token_program,
+        assert_vault_authority,
+    },
+};
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+pub fn refund_draw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    msg!("Entering the refund_draw instruction");
+
+    let accounts_iter = &mut accounts.iter();
+
+    let escrow_account = next_account_info(accounts_iter)?; // Escrow account holding the game state
+    let vault_authority = next_account_info(accounts_iter)?; // PDA owning the escrow token account
+    let escrow_token_account = next_account_info(accounts_iter)?; // Escrow token account holding both stakes
+    let mint = next_account_info(accounts_iter)?; // Mint of the staked token
+    let fund_token_account_player1 = next_account_info(accounts_iter)?; // Player 1's token account
+    let fund_token_account_player2 = next_account_info(accounts_iter)?; // Player 2's token account
+    let token_program = next_account_info(accounts_iter)?; // SPL Token or Token-2022 program owning the mint
+    let player1 = next_account_info(accounts_iter)?; // Player 1, who paid the game account's rent
+
+    assert_owned_by(escrow_account, program_id)?;
+
+    let mut game_state = GameState::try_from_slice(&escrow_account.try_borrow_data()?)?;
+    assert_game_address(escrow_account, program_id, &game_state)?;
+
+    // Only a matched game can be drawn; a game nobody joined is refunded through refund_unjoined
+    game_state.status.transition_to(GameStatus::Drawn)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    if now < game_state.expires_at {
+        msg!("Impossible to refund draw, game expires at {}", game_state.expires_at);
+        return Err(EscrowError::GameNotExpired.into());
+    }
+
+    // Anyone can send this instruction, so the refunds must go to the players' own accounts
+    assert_escrow_token_account(escrow_token_account, &game_state.escrow_token_account)?;
+    assert_vault_authority(vault_authority, program_id, escrow_account.key, game_state.vault_bump)?;
+    let decimals = assert_mint(mint, &game_state.mint)?.decimals;
+    assert_token_program(token_program, mint)?;
+    assert_token_owner(fund_token_account_player1, &game_state.player1)?;
+    assert_token_owner(fund_token_account_player2, &game_state.player2)?;
+    assert_token_mint(fund_token_account_player1, &game_state.mint)?;
+    assert_token_mint(fund_token_account_player2, &game_state.mint)?;
+    assert_rent_recipient(player1, &game_state)?;
+
+    // Both players put in the same stake; with a transfer-fee mint the odd unit goes to Player 1
+    let (player1_refund, player2_refund) = split_draw(game_state.deposited);
+
+    // The vault authority PDA signs for the escrow token account
+    let vault_seeds: &[&[u8]] = &[VAULT_SEED, escrow_account.key.as_ref(), &[game_state.vault_bump]];
+    for (token_account, amount) in [
+        (fund_token_account_player1, player1_refund),
+        (fund_token_account_player2, player2_refund),
+    ] {
+        transfer(
+            token_program,
+            escrow_token_account,
+            mint,
+            token_account,
+            vault_authority,
+            amount,
+            decimals,
+            &[vault_seeds],
+        )?;
+        msg!("Refunded {} to {}", amount, token_account.key);
+    }
+
+    // The game is over, so its vault and account go and their rent goes back to Player 1;
+    // any tokens left in the vault go to Player 1
+    close_vault(
+        token_program,
+        escrow_token_account,
+        mint,
+        fund_token_account_player1,
+        player1,
+        vault_authority,
+        decimals,
+        &[vault_seeds],
+    )?;
+    close_game_account(escrow_account, player1)?;
+
+    msg!("Game expired without a winner, both stakes refunded.");
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use crate::{
+    error::EscrowError,
+    pda::VAULT_SEED,
+    state::{
+        game_state::{close_game_account, GameState},
+        game_status::GameStatus,
+    },
+    token::{close_vault, transfer},
+    validation::{
+        assert_escrow_token_account, assert_game_address, assert_mint, assert_owned_by,
+        assert_rent_recipient, assert_token_mint, assert_token_owner, assert_token_program,
+        assert_vault_authority,
+    },
+};
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+pub fn refund_unjoined(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    msg!("Entering the refund_unjoined instruction");
+
+    let accounts_iter = &mut accounts.iter();
+
+    let escrow_account = next_account_info(accounts_iter)?; // Escrow account holding the game state
+    let vault_authority = next_account_info(accounts_iter)?; // PDA owning the escrow token account
+    let escrow_token_account = next_account_info(accounts_iter)?; // Escrow token account holding Player 1's stake
+    let mint = next_account_info(accounts_iter)?; // Mint of the staked token
+    let fund_token_account_player1 = next_account_info(accounts_iter)?; // Player 1's token account
+    let token_program = next_account_info(accounts_iter)?; // SPL Token or Token-2022 program owning the mint
+    let player1 = next_account_info(accounts_iter)?; // Player 1, who paid the game account's rent
+
+    assert_owned_by(escrow_account, program_id)?;
+
+    let mut game_state = GameState::try_from_slice(&escrow_account.try_borrow_data()?)?;
+    assert_game_address(escrow_account, program_id, &game_state)?;
+
+    // Once Player 2 joined, the stakes are settled or refunded as a draw instead
+    game_state.status.transition_to(GameStatus::Expired)?;
+
+    if Clock::get()?.unix_timestamp < game_state.join_deadline {
+        msg!("Impossible to refund, Player 2 can join until {}", game_state.join_deadline);
+        return Err(EscrowError::JoinDeadlineNotPassed.into());
+    }
+
+    // Anyone can send this instruction, so the refund must go to Player 1's own account
+    assert_token_owner(fund_token_account_player1, &game_state.player1)?;
+    assert_escrow_token_account(escrow_token_account, &game_state.escrow_token_account)?;
+    assert_vault_authority(vault_authority, program_id, escrow_account.key, game_state.vault_bump)?;
+    let decimals = assert_mint(mint, &game_state.mint)?.decimals;
+    assert_token_program(token_program, mint)?;
+    assert_token_mint(fund_token_account_player1, &game_state.mint)?;
+    assert_rent_recipient(player1, &game_state)?;
+
+    let usdc_amount = game_state.deposited; // Refund what landed in the vault from Player 1's stake
+
+    // The vault authority PDA signs for the escrow token account
+    let vault_seeds: &[&[u8]] = &[VAULT_SEED, escrow_account.key.as_ref(), &[game_state.vault_bump]];
+    transfer(
+        token_program,
+        escrow_token_account,
+        mint,
+        fund_token_account_player1,
+        vault_authority,
+        usdc_amount,
+        decimals,
+        &[vault_seeds],
+    )?;
+
+    // The game is over, so its vault and account go and their rent goes back to Player 1;
+    // any tokens left in the vault go to Player 1
+    close_vault(
+        token_program,
+        escrow_token_account,
+        mint,
+        fund_token_account_player1,
+        player1,
+        vault_authority,
+        decimals,
+        &[vault_seeds],
+    )?;
+    close_game_account(escrow_account, player1)?;
+
+    msg!("Nobody joined before the deadline, {} refunded to {}", usdc_amount, fund_token_account_player1.key);
+
+    Ok(())
+}
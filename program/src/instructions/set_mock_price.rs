@@ -0,0 +1,85 @@
+use crate::{
+    error::EscrowError,
+    pda::{find_mock_price_address, MOCK_PRICE_SEED},
+    state::mock_price::MockPrice,
+    validation::assert_signer,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+/// Creates or updates the mock price feed of the signer. Only compiled with the `mock-oracle` feature.
+pub fn set_mock_price(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    price: i64,
+    conf: u64,
+    expo: i32,
+) -> ProgramResult {
+
+    msg!("Entering the set_mock_price instruction");
+
+    let accounts_iter = &mut accounts.iter();
+
+    let authority = next_account_info(accounts_iter)?; // Owner of the mock feed, pays for it on first use
+    let mock_price_account = next_account_info(accounts_iter)?; // Mock feed, PDA of the authority
+    let system_program = next_account_info(accounts_iter)?; // System program
+
+    assert_signer(authority)?;
+
+    let (expected_mock_price_account, bump) = find_mock_price_address(program_id, authority.key);
+    if *mock_price_account.key != expected_mock_price_account {
+        msg!("Mock price account is not the PDA derived from the authority");
+        return Err(EscrowError::InvalidOracleAccount.into());
+    }
+
+    let mock_price = MockPrice {
+        authority: *authority.key,
+        price,
+        conf,
+        expo,
+        publish_time: Clock::get()?.unix_timestamp,
+    };
+    let mock_price_data = mock_price.try_to_vec()?;
+
+    // Create the feed the first time the authority sets a price
+    if mock_price_account.data_is_empty() {
+        let rent = Rent::get()?;
+        invoke_signed(
+            &system_instruction::create_account(
+                authority.key,
+                mock_price_account.key,
+                rent.minimum_balance(mock_price_data.len()),
+                mock_price_data.len() as u64,
+                program_id,
+            ),
+            &[
+                authority.clone(),
+                mock_price_account.clone(),
+                system_program.clone(),
+            ],
+            &[&[MOCK_PRICE_SEED, authority.key.as_ref(), &[bump]]],
+        )?;
+    } else {
+        let current = MockPrice::try_from_slice(&mock_price_account.try_borrow_data()?)?;
+        if current.authority != *authority.key {
+            return Err(EscrowError::Unauthorized.into());
+        }
+    }
+
+    mock_price_account
+        .try_borrow_mut_data()?
+        .copy_from_slice(&mock_price_data);
+
+    msg!("Mock price set to {}e{} +/- {}", price, expo, conf);
+
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use crate::validation::{assert_admin, assert_config};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+/// Halts or resumes games; refunds keep working while the program is paused
+pub fn set_paused(program_id: &Pubkey, accounts: &[AccountInfo], paused: bool) -> ProgramResult {
+
+    msg!("Entering the set_paused instruction");
+
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?; // Program admin
+    let config_account = next_account_info(accounts_iter)?; // Config PDA
+
+    let mut config = assert_config(config_account, program_id)?;
+    assert_admin(admin, &config)?;
+
+    config.paused = paused;
+    let config_data = config.try_to_vec()?;
+    config_account
+        .try_borrow_mut_data()?
+        .copy_from_slice(&config_data);
+
+    msg!("Program {}", if paused { "paused" } else { "resumed" });
+
+    Ok(())
+}
@@ -1,20 +1,29 @@
-use crate::{instructions::fetch_price::fetch_price, state::game_state::GameState};
+use crate::{
+    error::EscrowError,
+    instructions::fetch_price::fetch_price,
+    math::win_thresholds,
+    price::price_override,
+    state::{game_state::GameState, game_status::GameStatus},
+    token::unpack_token_account,
+    validation::{
+        assert_game_address, assert_not_paused, assert_oracle, assert_owned_by, assert_token_mint,
+        assert_token_owner,
+    },
+};
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::program_pack::Pack;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
     msg,
-    program_error::ProgramError,
     pubkey::Pubkey,
+    sysvar::Sysvar,
 };
 
-use spl_token::state::Account as TokenAccount;
-
 pub fn settle_game(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    instruction_data: &[u8],
+    last_price_got: u64,
 ) -> ProgramResult {
     
     msg!("Entering the settle_game instruction");
@@ -24,28 +33,30 @@ pub fn settle_game(
     let fund_token_account_player1 = next_account_info(accounts_iter)?; // Player 1's USDC token account
     let fund_token_account_player2 = next_account_info(accounts_iter)?; // Player 2's USDC token account
     let oracle_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?; // Program config
 
-    // Deserialize the current game state from the escrow account
-    let mut game_state = GameState::try_from_slice(&escrow_account.try_borrow_data()?)?;
+    // A misbehaving oracle must not decide games while the program is paused
+    assert_not_paused(config_account, program_id)?;
 
-    //msg!("game_state {:?}", game_state);
+    assert_owned_by(escrow_account, program_id)?;
 
-    let last_price_got = if instruction_data.len() >= 9 {
-        u64::from_le_bytes(instruction_data[1..9].try_into().unwrap())
-    } else {
-        0 // Default to 0 if last_price is not provided
-    };
+    // Deserialize the current game state from the escrow account
+    let mut game_state = GameState::try_from_slice(&escrow_account.try_borrow_data()?)?;
+    assert_game_address(escrow_account, program_id, &game_state)?;
+    assert_oracle(oracle_account, &game_state)?;
 
     msg!("last_price_got {:?}", last_price_got);
 
-    // Check if `last_price_got` is invalid (e.g., zero or a placeholder value), then fetch from the oracle
-    if instruction_data.len() < 9 || last_price_got == 0 {
-        msg!("last_price_got is invalid, fetching price from the oracle");
+    let last_price_override = price_override(last_price_got)?;
+
+    // Prices come from the oracle unless a test build was given an override
+    if last_price_override.is_none() {
+        msg!("No price override, fetching price from the oracle");
 
         // Fetch the price from the oracle and update game state (stored in escrow account)
         fetch_price(
             program_id,
-            &[oracle_account.clone(), escrow_account.clone()],
+            &[oracle_account.clone(), escrow_account.clone(), config_account.clone()],
         )?;
 
         // Re-fetch the updated game state from the escrow account
@@ -58,32 +69,34 @@ pub fn settle_game(
         msg!("Using manually provided last_price: {}", last_price_got);
     }
 
-    // Ensure the game is still active
-    if !game_state.game_active {
-        msg!("Impossible to settle game, game is inactive");
-        return Err(ProgramError::InvalidAccountData);
-    }
+    // Only a matched game can be settled, and only once
+    game_state.status.assert_transition(GameStatus::Settled)?;
 
-    // Ensure Player 2 is already set
-    if game_state.player2 == Pubkey::default() {
-        msg!("Impossible to settle game, there is not a player2");
-        return Err(ProgramError::InvalidAccountData);
+    // Once expired the game can only be refunded as a draw
+    if Clock::get()?.unix_timestamp >= game_state.expires_at {
+        msg!("Impossible to settle game, it expired at {}", game_state.expires_at);
+        return Err(EscrowError::GameExpired.into());
     }
 
-    // Mock price data (replace with actual data or oracle in real-world scenarios)
+    // The winner is read from these accounts, so they must belong to the players
+    assert_token_owner(fund_token_account_player1, &game_state.player1)?;
+    assert_token_owner(fund_token_account_player2, &game_state.player2)?;
+    assert_token_mint(fund_token_account_player1, &game_state.mint)?;
+    assert_token_mint(fund_token_account_player2, &game_state.mint)?;
+
+    // Compare the price read above with the entry price
     let price_at_start = game_state.entry_price;
-    //let current_price = price_at_start + (price_at_start / 10); // Simulate current price
-    let current_price = game_state.last_price; // Simulate current price
+    let current_price = game_state.last_price;
 
     // Price thresholds for determining the winner
-    let price_increase_threshold = price_at_start + (price_at_start * 5 / 100);
-    let price_decrease_threshold = price_at_start - (price_at_start * 5 / 100);
+    let (price_increase_threshold, price_decrease_threshold) =
+        win_thresholds(price_at_start, game_state.win_threshold_bps)?;
 
     let winner_token_account: &AccountInfo;
 
     // Determine the winner based on player choices and price change
     if current_price >= price_increase_threshold {
-        // If the price increased by 5%, check Player 1's choice
+        // If the price increased by the win threshold, check Player 1's choice
         if game_state.player1_choice {
             winner_token_account = fund_token_account_player1; // Player 1 wins (bet on increase)
             msg!("Player 1 wins with an increase bet.");
@@ -92,7 +105,7 @@ pub fn settle_game(
             msg!("Player 2 wins with a decrease bet.");
         }
     } else if current_price <= price_decrease_threshold {
-        // If the price decreased by 5%, check Player 2's choice
+        // If the price decreased by the win threshold, check Player 2's choice
         if !game_state.player1_choice {
             winner_token_account = fund_token_account_player1; // Player 1 wins (bet on decrease)
             msg!("Player 1 wins with a decrease bet.");
@@ -101,17 +114,16 @@ pub fn settle_game(
             msg!("Player 2 wins with an increase bet.");
         }
     } else {
-        msg!("There is not a winner");
+        msg!("There is not a winner yet, the game can be refunded as a draw from {}", game_state.expires_at);
         return Ok(());
-        //return Err(ProgramError::InvalidAccountData); // No winner yet (price has not changed enough)
     }
 
     msg!("Winner account {:?}", winner_token_account.key);
 
-    // Mark the game as inactive
-    game_state.game_active = false;
+    // The game is decided, only the payout is left
+    game_state.status.transition_to(GameStatus::Settled)?;
 
-    let token_account_data = TokenAccount::unpack(&winner_token_account.try_borrow_data()?)?;
+    let token_account_data = unpack_token_account(winner_token_account)?;
     let token_account_authority = token_account_data.owner;
 
     msg!(
@@ -0,0 +1,68 @@
+use crate::{
+    math::add_seconds,
+    state::config::ConfigSettings,
+    validation::{assert_admin, assert_config, assert_config_settings},
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+/// Queues new settings, applied by `apply_config_update` once the config's timelock has elapsed
+#[allow(clippy::too_many_arguments)]
+pub fn update_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    allowed_mints: Vec<Pubkey>,
+    default_max_join_drift_bps: u16,
+    default_win_threshold_bps: u16,
+    fee_bps: u16,
+    treasury: Pubkey,
+    timelock_delay: i64,
+) -> ProgramResult {
+
+    msg!("Entering the update_config instruction");
+
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?; // Program admin
+    let config_account = next_account_info(accounts_iter)?; // Config PDA
+
+    let mut config = assert_config(config_account, program_id)?;
+    assert_admin(admin, &config)?;
+
+    let mut settings = ConfigSettings {
+        default_max_join_drift_bps,
+        default_win_threshold_bps,
+        fee_bps,
+        treasury,
+        timelock_delay,
+        ..ConfigSettings::default()
+    };
+    settings.set_allowed_mints(&allowed_mints)?;
+    assert_config_settings(&settings)?;
+
+    // The delay in force applies, so shortening it also waits out the current one
+    let effective_at = add_seconds(Clock::get()?.unix_timestamp, config.settings.timelock_delay)?;
+    config.queue_settings(settings, effective_at);
+
+    let config_data = config.try_to_vec()?;
+    config_account
+        .try_borrow_mut_data()?
+        .copy_from_slice(&config_data);
+
+    msg!(
+        "Config update queued until {}: {} allowed mints, fee {} bps to {}",
+        effective_at,
+        allowed_mints.len(),
+        fee_bps,
+        treasury
+    );
+
+    Ok(())
+}
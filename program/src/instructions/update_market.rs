@@ -0,0 +1,79 @@
+use crate::{
+    math::add_seconds,
+    state::market_registry::Market,
+    validation::{assert_admin, assert_config, assert_market_parameters, assert_market_registry},
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+/// Queues new parameters for a market, applied by `apply_market_update` once the
+/// config's timelock has elapsed; open games keep their own copy. Deactivating a
+/// market takes effect at once.
+pub fn update_market(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    market_id: u8,
+    max_join_drift_bps: u16,
+    win_threshold_bps: u16,
+    active: bool,
+) -> ProgramResult {
+
+    msg!("Entering the update_market instruction");
+
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?; // Program admin
+    let registry_account = next_account_info(accounts_iter)?; // Market registry PDA
+    let config_account = next_account_info(accounts_iter)?; // Program config naming the admin
+
+    let config = assert_config(config_account, program_id)?;
+    assert_admin(admin, &config)?;
+    let mut registry = assert_market_registry(registry_account, program_id)?;
+
+    let market = Market {
+        max_join_drift_bps,
+        win_threshold_bps,
+        active,
+        ..*registry.market(market_id)?
+    };
+    assert_market_parameters(max_join_drift_bps, win_threshold_bps)?;
+
+    // Stopping new games on a market cannot wait, e.g. when its feed misbehaves,
+    // and a change queued earlier must not reactivate it behind the admin's back
+    if !active {
+        registry.markets[market_id as usize].active = false;
+        if registry.has_pending_market && registry.pending_market_id == market_id {
+            msg!("Dropping the change queued for market {}", market_id);
+            registry.clear_pending_market();
+        }
+        msg!("Market {} deactivated", market.symbol());
+    }
+
+    // Activating a market or changing its parameters waits out the timelock
+    if market != registry.markets[market_id as usize] {
+        let effective_at = add_seconds(Clock::get()?.unix_timestamp, config.settings.timelock_delay)?;
+        registry.queue_market(market_id, market, effective_at);
+        msg!(
+            "Market {} update queued until {}: join drift {} bps, win threshold {} bps, active {}",
+            market.symbol(),
+            effective_at,
+            max_join_drift_bps,
+            win_threshold_bps,
+            active
+        );
+    }
+
+    let registry_data = registry.try_to_vec()?;
+    registry_account
+        .try_borrow_mut_data()?
+        .copy_from_slice(&registry_data);
+
+    Ok(())
+}
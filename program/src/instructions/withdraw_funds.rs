@@ -1,18 +1,27 @@
-use crate::state::game_state::GameState;
-use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    error::EscrowError,
+    pda::VAULT_SEED,
+    state::{
+        game_state::{close_game_account, GameState},
+        game_status::GameStatus,
+    },
+    token::{close_vault, transfer},
+    validation::{
+        assert_escrow_token_account, assert_game_address, assert_mint, assert_owned_by,
+        assert_signer, assert_token_mint, assert_token_owner, assert_token_program,
+        assert_vault_authority,
+    },
+};
+use borsh::BorshDeserialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
-    program::invoke,
-    program_error::ProgramError,
     pubkey::Pubkey,
 };
 
-use spl_token::instruction::transfer as spl_transfer;
-
 pub fn withdraw_funds(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
 
@@ -20,47 +29,65 @@ pub fn withdraw_funds(
 
     let accounts_iter = &mut accounts.iter();
 
-    //let payer = next_account_info(accounts_iter)?; // Player 1
+    let player1 = next_account_info(accounts_iter)?; // Player 1, who also gets the game account's rent back
     let escrow_account = next_account_info(accounts_iter)?; // Escrow account holding the game state
-    let escrow_token_account_authority = next_account_info(accounts_iter)?;
+    let vault_authority = next_account_info(accounts_iter)?; // PDA owning the escrow token account
     let escrow_token_account = next_account_info(accounts_iter)?; // Escrow token account holding USDC
+    let mint = next_account_info(accounts_iter)?; // Mint of the staked token
     let fund_token_account_player1 = next_account_info(accounts_iter)?; // Player 1's USDC token account
-    let token_program = next_account_info(accounts_iter)?; // SPL token program
+    let token_program = next_account_info(accounts_iter)?; // SPL Token or Token-2022 program owning the mint
+
+    assert_signer(player1)?;
+    assert_owned_by(escrow_account, program_id)?;
 
     let mut game_state = GameState::try_from_slice(&escrow_account.try_borrow_data()?)?;
-    // Ensure Player 2 is not already set
-    if game_state.player2 != Pubkey::default() {
-        msg!("Impossible to withdraw: Player 2 already exists, withdrawal not allowed.");
-        return Err(ProgramError::InvalidAccountData); // Return an error indicating Player 2 is already set
+    assert_game_address(escrow_account, program_id, &game_state)?;
+
+    // Only Player 1 can withdraw, and only into a token account they own
+    if *player1.key != game_state.player1 {
+        msg!("Impossible to withdraw: signer is not Player 1.");
+        return Err(EscrowError::Unauthorized.into());
     }
+    assert_token_owner(fund_token_account_player1, &game_state.player1)?;
+    assert_escrow_token_account(escrow_token_account, &game_state.escrow_token_account)?;
+    assert_vault_authority(vault_authority, program_id, escrow_account.key, game_state.vault_bump)?;
+    let decimals = assert_mint(mint, &game_state.mint)?.decimals;
+    assert_token_program(token_program, mint)?;
+    assert_token_mint(fund_token_account_player1, &game_state.mint)?;
+
+    // Player 1 can only take their stake back while nobody has joined
+    game_state.status.transition_to(GameStatus::Cancelled)?;
 
-    let usdc_amount: u64 = 1000_000_000; // USDC Token created has 9 decimals, so 1000 USDC is represented as 1,000,000,000 in smallest units
+    let usdc_amount = game_state.deposited; // Refund what landed in the vault from Player 1's stake
 
-    // Ensure the escrow_token_account has the correct authority and ownership for SPL transfers
-    invoke(
-        &spl_transfer(
-            token_program.key,                  // SPL token program
-            escrow_token_account.key,           // Source account (escrow token account with USDC)
-            fund_token_account_player1.key,     // Destination account (winner's USDC token account)
-            escrow_token_account_authority.key, // Authority (payer’s account)
-            &[],                                // No additional signers
-            usdc_amount,                        // Amount of USDC to transfer
-        )?,
-        &[
-            escrow_token_account_authority.clone(),
-            escrow_token_account.clone(),
-            fund_token_account_player1.clone(),
-            token_program.clone(),
-        ],
+    // The vault authority PDA signs for the escrow token account
+    let vault_seeds: &[&[u8]] = &[VAULT_SEED, escrow_account.key.as_ref(), &[game_state.vault_bump]];
+    let received = transfer(
+        token_program,
+        escrow_token_account,
+        mint,
+        fund_token_account_player1,
+        vault_authority,
+        usdc_amount,
+        decimals,
+        &[vault_seeds],
     )?;
 
-    game_state.game_active = false;
-    let game_state_data = game_state.try_to_vec()?; // Convert GameState to a byte vector
-    escrow_account
-        .try_borrow_mut_data()?
-        .copy_from_slice(&game_state_data);
+    // The game is over, so its vault and account go and their rent goes back to Player 1;
+    // any tokens left in the vault go to Player 1
+    close_vault(
+        token_program,
+        escrow_token_account,
+        mint,
+        fund_token_account_player1,
+        player1,
+        vault_authority,
+        decimals,
+        &[vault_seeds],
+    )?;
+    close_game_account(escrow_account, player1)?;
 
-    msg!("Funds withdrawn successfully: {} USDC transferred from escrow to {}", usdc_amount, fund_token_account_player1.key);
+    msg!("Funds withdrawn successfully: {} transferred from escrow to {}, {} received", usdc_amount, fund_token_account_player1.key, received);
 
     Ok(())
 }
@@ -1,10 +1,20 @@
+use crate::error::EscrowError;
 use solana_program::{
-    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, pubkey::Pubkey,
+    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult,
+    program_error::PrintProgramError, pubkey::Pubkey,
 };
 
+pub mod error;
+pub mod instruction;
 pub mod instructions;
+pub mod math;
+pub mod oracle;
+pub mod pda;
+pub mod price;
 pub mod processor;
 pub mod state;
+pub mod token;
+pub mod validation;
 
 entrypoint!(process_instruction);
 
@@ -13,5 +23,10 @@ fn process_instruction(
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    processor::Processor::process(program_id, accounts, instruction_data)
+    if let Err(error) = processor::Processor::process(program_id, accounts, instruction_data) {
+        // Log the custom error name before returning the code to the runtime
+        error.print::<EscrowError>();
+        return Err(error);
+    }
+    Ok(())
 }
@@ -0,0 +1,211 @@
+use crate::error::EscrowError;
+use solana_program::{msg, program_error::ProgramError};
+
+/// Basis points in a whole
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Adds two amounts or prices, failing instead of wrapping
+pub fn checked_add(a: u64, b: u64) -> Result<u64, ProgramError> {
+    a.checked_add(b).ok_or_else(|| overflow("add", a, b))
+}
+
+/// Subtracts `b` from `a`, failing instead of wrapping below zero
+pub fn checked_sub(a: u64, b: u64) -> Result<u64, ProgramError> {
+    a.checked_sub(b).ok_or_else(|| overflow("subtract", a, b))
+}
+
+/// Moves a Unix timestamp by `seconds`, failing past the range of `i64`
+pub fn add_seconds(timestamp: i64, seconds: i64) -> Result<i64, ProgramError> {
+    timestamp.checked_add(seconds).ok_or_else(|| {
+        msg!("Timestamp {} + {} seconds is out of range", timestamp, seconds);
+        EscrowError::ArithmeticOverflow.into()
+    })
+}
+
+/// `amount * bps / 10_000`, rounded down.
+///
+/// Computed in `u128`, so it only fails when `bps` is above 10_000 and the
+/// result does not fit a `u64`.
+pub fn bps_of(amount: u64, bps: u16) -> Result<u64, ProgramError> {
+    let part = amount as u128 * bps as u128 / BPS_DENOMINATOR as u128;
+    u64::try_from(part).map_err(|_| overflow("take bps of", amount, bps as u64))
+}
+
+/// Whether `part` is more than `bps` basis points of `whole`.
+///
+/// Compared as `part * 10_000 > whole * bps` in `u128`, which cannot overflow
+/// and loses no precision to division.
+pub fn exceeds_bps(part: u64, whole: u64, bps: u16) -> bool {
+    part as u128 * BPS_DENOMINATOR as u128 > whole as u128 * bps as u128
+}
+
+/// Prices at or beyond which a game entered at `entry_price` is won, as
+/// `(increase, decrease)`.
+///
+/// Fails if the increase threshold does not fit a `u64`; the decrease
+/// threshold cannot go below zero for thresholds up to 10_000 bps.
+pub fn win_thresholds(
+    entry_price: u64,
+    win_threshold_bps: u16,
+) -> Result<(u64, u64), ProgramError> {
+    let win_move = bps_of(entry_price, win_threshold_bps)?;
+    Ok((
+        checked_add(entry_price, win_move)?,
+        checked_sub(entry_price, win_move)?,
+    ))
+}
+
+/// Splits the vault of a drawn game as `(player1, player2)`. Player 1 gets the
+/// odd unit, so the two refunds always add up to `deposited`.
+pub fn split_draw(deposited: u64) -> (u64, u64) {
+    let player2_refund = deposited / 2;
+    (deposited - player2_refund, player2_refund)
+}
+
+/// Splits a payout of `amount` as `(winner, fee)`, the fee being `fee_bps` of
+/// the amount rounded down. The two always add up to `amount`.
+pub fn split_payout(amount: u64, fee_bps: u16) -> Result<(u64, u64), ProgramError> {
+    let fee = bps_of(amount, fee_bps)?;
+    Ok((checked_sub(amount, fee)?, fee))
+}
+
+fn overflow(operation: &str, a: u64, b: u64) -> ProgramError {
+    msg!("Arithmetic overflow: cannot {} {} and {}", operation, a, b);
+    EscrowError::ArithmeticOverflow.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::config::MAX_FEE_BPS;
+    use proptest::prelude::*;
+
+    fn overflow_error() -> ProgramError {
+        EscrowError::ArithmeticOverflow.into()
+    }
+
+    proptest! {
+        #[test]
+        fn add_matches_wide_sum_or_overflows(a in any::<u64>(), b in any::<u64>()) {
+            let wide = a as u128 + b as u128;
+            match checked_add(a, b) {
+                Ok(sum) => prop_assert_eq!(sum as u128, wide),
+                Err(error) => {
+                    prop_assert!(wide > u64::MAX as u128);
+                    prop_assert_eq!(error, overflow_error());
+                }
+            }
+        }
+
+        #[test]
+        fn sub_matches_difference_or_overflows(a in any::<u64>(), b in any::<u64>()) {
+            match checked_sub(a, b) {
+                Ok(difference) => prop_assert_eq!(difference + b, a),
+                Err(error) => {
+                    prop_assert!(b > a);
+                    prop_assert_eq!(error, overflow_error());
+                }
+            }
+        }
+
+        #[test]
+        fn add_seconds_matches_wide_sum_or_overflows(timestamp in any::<i64>(), seconds in any::<i64>()) {
+            let wide = timestamp as i128 + seconds as i128;
+            match add_seconds(timestamp, seconds) {
+                Ok(moved) => prop_assert_eq!(moved as i128, wide),
+                Err(error) => {
+                    prop_assert!(wide > i64::MAX as i128 || wide < i64::MIN as i128);
+                    prop_assert_eq!(error, overflow_error());
+                }
+            }
+        }
+
+        #[test]
+        fn bps_of_never_exceeds_amount_up_to_whole(amount in any::<u64>(), bps in 0..=10_000u16) {
+            let part = bps_of(amount, bps).unwrap();
+            prop_assert!(part <= amount);
+            prop_assert_eq!(part as u128, amount as u128 * bps as u128 / 10_000);
+        }
+
+        #[test]
+        fn bps_of_overflows_only_beyond_u64(amount in any::<u64>(), bps in any::<u16>()) {
+            let wide = amount as u128 * bps as u128 / 10_000;
+            match bps_of(amount, bps) {
+                Ok(part) => prop_assert_eq!(part as u128, wide),
+                Err(error) => {
+                    prop_assert!(wide > u64::MAX as u128);
+                    prop_assert_eq!(error, overflow_error());
+                }
+            }
+        }
+
+        #[test]
+        fn exceeds_bps_matches_exact_ratio(part in any::<u64>(), whole in any::<u64>(), bps in any::<u16>()) {
+            // part / whole > bps / 10_000, cross-multiplied in a type wide enough for any input
+            let expected = (part as u128) * 10_000 > (whole as u128) * (bps as u128);
+            prop_assert_eq!(exceeds_bps(part, whole, bps), expected);
+        }
+
+        #[test]
+        fn exceeds_bps_accepts_exact_boundary(whole in 0..=u64::MAX / 10_000, bps in 0..=10_000u16) {
+            // whole * bps / 10_000 is exact when whole is a multiple of 10_000
+            let whole = whole * 10_000;
+            let boundary = (whole as u128 * bps as u128 / 10_000) as u64;
+            prop_assert!(!exceeds_bps(boundary, whole, bps));
+            prop_assert!(exceeds_bps(boundary + 1, whole, bps));
+        }
+
+        #[test]
+        fn win_thresholds_surround_entry_price(entry_price in any::<u64>(), bps in 0..=10_000u16) {
+            match win_thresholds(entry_price, bps) {
+                Ok((increase, decrease)) => {
+                    prop_assert!(decrease <= entry_price && entry_price <= increase);
+                    prop_assert_eq!(increase - entry_price, entry_price - decrease);
+                }
+                Err(error) => {
+                    let win_move = entry_price as u128 * bps as u128 / 10_000;
+                    prop_assert!(entry_price as u128 + win_move > u64::MAX as u128);
+                    prop_assert_eq!(error, overflow_error());
+                }
+            }
+        }
+
+        #[test]
+        fn split_payout_conserves_vault(amount in any::<u64>(), fee_bps in 0..=10_000u16) {
+            let (winner, fee) = split_payout(amount, fee_bps).unwrap();
+            prop_assert_eq!(winner as u128 + fee as u128, amount as u128);
+            prop_assert_eq!(fee as u128, amount as u128 * fee_bps as u128 / 10_000);
+        }
+
+        #[test]
+        fn split_payout_fee_within_bound(amount in any::<u64>(), fee_bps in 0..=MAX_FEE_BPS) {
+            let (winner, fee) = split_payout(amount, fee_bps).unwrap();
+            prop_assert!(fee as u128 * 10_000 <= amount as u128 * MAX_FEE_BPS as u128);
+            prop_assert!(winner as u128 * 10_000 >= amount as u128 * (10_000 - MAX_FEE_BPS) as u128);
+        }
+
+        #[test]
+        fn split_draw_conserves_deposit(deposited in any::<u64>()) {
+            let (player1_refund, player2_refund) = split_draw(deposited);
+            prop_assert_eq!(player1_refund as u128 + player2_refund as u128, deposited as u128);
+            prop_assert!(player1_refund - player2_refund <= 1);
+        }
+    }
+
+    #[test]
+    fn extremes_of_u64() {
+        assert_eq!(checked_add(u64::MAX, 0), Ok(u64::MAX));
+        assert_eq!(checked_add(u64::MAX, 1), Err(overflow_error()));
+        assert_eq!(checked_sub(0, 1), Err(overflow_error()));
+        assert_eq!(bps_of(u64::MAX, 10_000), Ok(u64::MAX));
+        assert_eq!(bps_of(u64::MAX, 10_001), Err(overflow_error()));
+        assert_eq!(win_thresholds(u64::MAX, 0), Ok((u64::MAX, u64::MAX)));
+        assert_eq!(win_thresholds(u64::MAX, 1), Err(overflow_error()));
+        assert_eq!(win_thresholds(u64::MAX / 2, 10_000), Ok((u64::MAX - 1, 0)));
+        assert_eq!(split_draw(u64::MAX), (u64::MAX / 2 + 1, u64::MAX / 2));
+        assert_eq!(split_payout(u64::MAX, 0), Ok((u64::MAX, 0)));
+        assert_eq!(split_payout(u64::MAX, 10_000), Ok((0, u64::MAX)));
+        assert_eq!(split_payout(2_000_000_000, 250), Ok((1_950_000_000, 50_000_000)));
+        assert_eq!(split_payout(1, 9_999), Ok((1, 0)));
+    }
+}
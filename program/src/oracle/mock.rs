@@ -0,0 +1,50 @@
+use super::{OraclePrice, PriceSource};
+use crate::{error::EscrowError, state::mock_price::MockPrice};
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Reads the program-owned feeds set with `SetMockPrice`, in builds with the `mock-oracle` feature
+pub struct MockSource;
+
+impl MockSource {
+    fn load(program_id: &Pubkey, oracle_account: &AccountInfo) -> Result<MockPrice, ProgramError> {
+        if !cfg!(feature = "mock-oracle") {
+            msg!("Mock price feeds are not available in this build");
+            return Err(EscrowError::InvalidOracleAccount.into());
+        }
+        if oracle_account.owner != program_id {
+            msg!("Mock price feed {} is not owned by this program", oracle_account.key);
+            return Err(EscrowError::InvalidOracleAccount.into());
+        }
+        MockPrice::try_from_slice(&oracle_account.try_borrow_data()?)
+            .map_err(|_| EscrowError::InvalidOracleAccount.into())
+    }
+}
+
+impl PriceSource for MockSource {
+    fn validate(&self, program_id: &Pubkey, oracle_account: &AccountInfo) -> ProgramResult {
+        Self::load(program_id, oracle_account)?;
+        Ok(())
+    }
+
+    fn owner(&self, program_id: &Pubkey) -> Pubkey {
+        *program_id
+    }
+
+    fn read_price(
+        &self,
+        program_id: &Pubkey,
+        oracle_account: &AccountInfo,
+    ) -> Result<OraclePrice, ProgramError> {
+        let mock_price = Self::load(program_id, oracle_account)?;
+        Ok(OraclePrice {
+            price: mock_price.price,
+            conf: mock_price.conf,
+            expo: mock_price.expo,
+            publish_time: mock_price.publish_time,
+        })
+    }
+}
@@ -0,0 +1,63 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+pub mod mock;
+pub mod pyth;
+pub mod switchboard;
+
+use mock::MockSource;
+use pyth::PythSource;
+use switchboard::SwitchboardSource;
+
+/// A price read from a feed: `price * 10^expo`, give or take `conf * 10^expo`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64, // Unix timestamp the price was published at
+}
+
+/// A kind of price feed account the program can read
+pub trait PriceSource {
+    /// Ensures the account is a feed of this kind, before it is pinned for a game
+    fn validate(&self, program_id: &Pubkey, oracle_account: &AccountInfo) -> ProgramResult;
+
+    /// Program owning every feed of this kind
+    fn owner(&self, program_id: &Pubkey) -> Pubkey;
+
+    /// Reads the feed's current price
+    fn read_price(
+        &self,
+        program_id: &Pubkey,
+        oracle_account: &AccountInfo,
+    ) -> Result<OraclePrice, ProgramError>;
+}
+
+/// Kind of feed a game is priced by, chosen at creation.
+///
+/// The variant order is the Borsh encoding stored in games, so new variants must only ever be appended.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OracleType {
+    /// Pyth price account
+    #[default]
+    Pyth,
+    /// Switchboard V2 aggregator account
+    Switchboard,
+    /// Mock feed owned by this program, only readable with the `mock-oracle` feature
+    Mock,
+}
+
+impl OracleType {
+    /// Reader for feeds of this kind
+    pub fn source(self) -> &'static dyn PriceSource {
+        match self {
+            OracleType::Pyth => &PythSource,
+            OracleType::Switchboard => &SwitchboardSource,
+            OracleType::Mock => &MockSource,
+        }
+    }
+}
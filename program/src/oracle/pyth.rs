@@ -0,0 +1,66 @@
+use super::{OraclePrice, PriceSource};
+use crate::error::EscrowError;
+use pyth_sdk_solana::state::load_price_account;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    pubkey, pubkey::Pubkey,
+};
+
+/// Pyth oracle program on mainnet, owner of every price account
+#[cfg(not(feature = "devnet"))]
+pub const PYTH_PROGRAM_ID: Pubkey = pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+
+/// Pyth oracle program on devnet, owner of every price account
+#[cfg(feature = "devnet")]
+pub const PYTH_PROGRAM_ID: Pubkey = pubkey!("gSbePebfvPy7tRqimPoVecS2UsBvYv46ynrzWocc92s");
+
+/// Reads Pyth price accounts
+pub struct PythSource;
+
+impl PythSource {
+    /// Ensures the account belongs to the Pyth program, since anyone can write
+    /// a price account in Pyth's format to an account they own
+    fn assert_pyth_owner(oracle_account: &AccountInfo) -> ProgramResult {
+        if *oracle_account.owner != PYTH_PROGRAM_ID {
+            msg!("Price account {} is not owned by the Pyth program", oracle_account.key);
+            return Err(EscrowError::InvalidOracleAccount.into());
+        }
+        Ok(())
+    }
+}
+
+impl PriceSource for PythSource {
+    fn validate(&self, _program_id: &Pubkey, oracle_account: &AccountInfo) -> ProgramResult {
+        Self::assert_pyth_owner(oracle_account)?;
+        load_price_account(&oracle_account.try_borrow_data()?)
+            .map_err(|_| EscrowError::InvalidOracleAccount)?;
+        Ok(())
+    }
+
+    fn owner(&self, _program_id: &Pubkey) -> Pubkey {
+        PYTH_PROGRAM_ID
+    }
+
+    /// pyth-sdk-solana 0.2 predates `get_price_no_older_than` and still calls the
+    /// aggregate's publish timestamp `drv1`, so staleness is checked by the caller.
+    fn read_price(
+        &self,
+        _program_id: &Pubkey,
+        oracle_account: &AccountInfo,
+    ) -> Result<OraclePrice, ProgramError> {
+        Self::assert_pyth_owner(oracle_account)?;
+        let data = oracle_account.try_borrow_data()?;
+        let price_account =
+            load_price_account(&data).map_err(|_| EscrowError::InvalidOracleAccount)?;
+        let price = price_account
+            .to_price_feed(oracle_account.key)
+            .get_current_price()
+            .ok_or(EscrowError::PriceUnavailable)?;
+        Ok(OraclePrice {
+            price: price.price,
+            conf: price.conf,
+            expo: price.expo,
+            publish_time: price_account.drv1,
+        })
+    }
+}
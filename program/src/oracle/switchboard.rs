@@ -0,0 +1,189 @@
+use super::{OraclePrice, PriceSource};
+use crate::{error::EscrowError, price::PRICE_EXPO};
+use std::cell::Ref;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    pubkey, pubkey::Pubkey,
+};
+
+/// Switchboard V2 program, owner of every aggregator account
+pub const SWITCHBOARD_PROGRAM_ID: Pubkey = pubkey!("SW1TCH7qEPTdLsDHRgPuMQjbQxKdH2aBStViMFnt64f");
+
+/// Anchor discriminator of `AggregatorAccountData`
+const AGGREGATOR_DISCRIMINATOR: [u8; 8] = [217, 230, 65, 101, 201, 162, 27, 125];
+
+// Byte offsets into the packed `AggregatorAccountData`, discriminator included
+const MIN_ORACLE_RESULTS_OFFSET: usize = 236;
+const ROUND_NUM_SUCCESS_OFFSET: usize = 341;
+const ROUND_OPEN_TIMESTAMP_OFFSET: usize = 358;
+const ROUND_RESULT_OFFSET: usize = 366;
+const ROUND_STD_DEVIATION_OFFSET: usize = 386;
+const AGGREGATOR_MIN_LEN: usize = 406;
+
+/// Reads Switchboard V2 aggregator accounts.
+///
+/// The account is decoded by hand because the Switchboard crates do not build
+/// against this program's Solana version; only the latest confirmed round is read.
+pub struct SwitchboardSource;
+
+/// Decodes the `SwitchboardDecimal` (`i128` mantissa, `u32` scale) at `offset`
+fn read_decimal(data: &[u8], offset: usize) -> (i128, u32) {
+    let mantissa = i128::from_le_bytes(data[offset..offset + 16].try_into().unwrap());
+    let scale = u32::from_le_bytes(data[offset + 16..offset + 20].try_into().unwrap());
+    (mantissa, scale)
+}
+
+/// Rescales `mantissa * 10^-from` to a mantissa with scale `to`, truncating extra digits
+fn rescale(mantissa: i128, from: u32, to: u32) -> Option<i128> {
+    if from >= to {
+        10i128.checked_pow(from - to).map(|factor| mantissa / factor)
+    } else {
+        10i128.checked_pow(to - from).and_then(|factor| mantissa.checked_mul(factor))
+    }
+}
+
+impl SwitchboardSource {
+    fn load<'a>(oracle_account: &'a AccountInfo) -> Result<Ref<'a, &'a mut [u8]>, ProgramError> {
+        if *oracle_account.owner != SWITCHBOARD_PROGRAM_ID {
+            msg!("Aggregator {} is not owned by the Switchboard program", oracle_account.key);
+            return Err(EscrowError::InvalidOracleAccount.into());
+        }
+        let data = oracle_account.try_borrow_data()?;
+        if data.len() < AGGREGATOR_MIN_LEN || data[..8] != AGGREGATOR_DISCRIMINATOR {
+            msg!("Account {} is not a Switchboard aggregator", oracle_account.key);
+            return Err(EscrowError::InvalidOracleAccount.into());
+        }
+        Ok(data)
+    }
+}
+
+impl PriceSource for SwitchboardSource {
+    fn validate(&self, _program_id: &Pubkey, oracle_account: &AccountInfo) -> ProgramResult {
+        Self::load(oracle_account)?;
+        Ok(())
+    }
+
+    fn owner(&self, _program_id: &Pubkey) -> Pubkey {
+        SWITCHBOARD_PROGRAM_ID
+    }
+
+    fn read_price(
+        &self,
+        _program_id: &Pubkey,
+        oracle_account: &AccountInfo,
+    ) -> Result<OraclePrice, ProgramError> {
+        let data = Self::load(oracle_account)?;
+
+        // A round is only valid once enough oracles responded
+        let read_u32 =
+            |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+        if read_u32(ROUND_NUM_SUCCESS_OFFSET) < read_u32(MIN_ORACLE_RESULTS_OFFSET) {
+            msg!("Switchboard aggregator has no confirmed result");
+            return Err(EscrowError::PriceUnavailable.into());
+        }
+
+        let (mantissa, scale) = read_decimal(&data, ROUND_RESULT_OFFSET);
+        let (std_mantissa, std_scale) = read_decimal(&data, ROUND_STD_DEVIATION_OFFSET);
+        let publish_time = i64::from_le_bytes(
+            data[ROUND_OPEN_TIMESTAMP_OFFSET..ROUND_OPEN_TIMESTAMP_OFFSET + 8]
+                .try_into()
+                .unwrap(),
+        );
+
+        // Results carry up to 28 digits of scale, too many for an i64 mantissa, so bring
+        // them down to the program's precision, or lower while the mantissa still overflows
+        let mut price_scale = scale.min(PRICE_EXPO.unsigned_abs());
+        let price = loop {
+            let rescaled =
+                rescale(mantissa, scale, price_scale).ok_or(EscrowError::InvalidOracleAccount)?;
+            match i64::try_from(rescaled) {
+                Ok(price) => break price,
+                Err(_) if price_scale > 0 => price_scale -= 1,
+                Err(_) => return Err(EscrowError::InvalidOracleAccount.into()),
+            }
+        };
+
+        // Express the result like a Pyth price, with the standard deviation as the confidence
+        let conf = rescale(std_mantissa, std_scale, price_scale)
+            .and_then(|conf| u64::try_from(conf.unsigned_abs()).ok())
+            .ok_or(EscrowError::InvalidOracleAccount)?;
+        let expo = -(price_scale as i32);
+        Ok(OraclePrice {
+            price,
+            conf,
+            expo,
+            publish_time,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a confirmed aggregator round holding `result` and `std_deviation`
+    fn aggregator_data(
+        result: (i128, u32),
+        std_deviation: (i128, u32),
+        publish_time: i64,
+    ) -> Vec<u8> {
+        let mut data = vec![0u8; AGGREGATOR_MIN_LEN];
+        data[..8].copy_from_slice(&AGGREGATOR_DISCRIMINATOR);
+        data[MIN_ORACLE_RESULTS_OFFSET..MIN_ORACLE_RESULTS_OFFSET + 4]
+            .copy_from_slice(&1u32.to_le_bytes());
+        data[ROUND_NUM_SUCCESS_OFFSET..ROUND_NUM_SUCCESS_OFFSET + 4]
+            .copy_from_slice(&1u32.to_le_bytes());
+        data[ROUND_OPEN_TIMESTAMP_OFFSET..ROUND_OPEN_TIMESTAMP_OFFSET + 8]
+            .copy_from_slice(&publish_time.to_le_bytes());
+        for (offset, (mantissa, scale)) in [
+            (ROUND_RESULT_OFFSET, result),
+            (ROUND_STD_DEVIATION_OFFSET, std_deviation),
+        ] {
+            data[offset..offset + 16].copy_from_slice(&mantissa.to_le_bytes());
+            data[offset + 16..offset + 20].copy_from_slice(&scale.to_le_bytes());
+        }
+        data
+    }
+
+    fn read(data: &mut [u8]) -> Result<OraclePrice, ProgramError> {
+        let key = Pubkey::new_unique();
+        let owner = SWITCHBOARD_PROGRAM_ID;
+        let mut lamports = 0;
+        let account = AccountInfo::new(&key, false, false, &mut lamports, data, &owner, false, 0);
+        SwitchboardSource.read_price(&Pubkey::new_unique(), &account)
+    }
+
+    #[test]
+    fn read_price_rescales_28_digit_results() {
+        // 2500.5 ± 1.25, both at the largest scale rust_decimal allows
+        let mut data = aggregator_data(
+            (25_005 * 10i128.pow(27), 28),
+            (125 * 10i128.pow(26), 28),
+            1_700_000_000,
+        );
+        assert_eq!(
+            read(&mut data),
+            Ok(OraclePrice {
+                price: 250_050_000_000,
+                conf: 125_000_000,
+                expo: PRICE_EXPO,
+                publish_time: 1_700_000_000,
+            })
+        );
+    }
+
+    #[test]
+    fn read_price_keeps_small_scales() {
+        let mut data = aggregator_data((25_005, 1), (0, 0), 1_700_000_000);
+        let price = read(&mut data).unwrap();
+        assert_eq!((price.price, price.expo), (25_005, -1));
+    }
+
+    #[test]
+    fn read_price_drops_digits_until_the_mantissa_fits() {
+        // 10^11 with 8 decimals overflows an i64 mantissa, 7 decimals fit
+        let mut data = aggregator_data((10i128.pow(27), 16), (0, 0), 0);
+        let price = read(&mut data).unwrap();
+        assert_eq!((price.price, price.expo), (10i64.pow(18), -7));
+    }
+}
@@ -0,0 +1,58 @@
+use solana_program::{bpf_loader_upgradeable, pubkey::Pubkey};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+/// Seed prefix of a game state account
+pub const GAME_SEED: &[u8] = b"game";
+
+/// Seed prefix of the PDA that owns a game's escrow token account
+pub const VAULT_SEED: &[u8] = b"vault";
+
+/// Seed prefix of a mock price feed, only read by builds with the `mock-oracle` feature
+pub const MOCK_PRICE_SEED: &[u8] = b"mock_price";
+
+/// Seed of the market registry
+pub const MARKET_REGISTRY_SEED: &[u8] = b"markets";
+
+/// Seed of the program config
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// Derives the game state account of `player1` for a client-chosen nonce
+pub fn find_game_address(program_id: &Pubkey, player1: &Pubkey, nonce: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GAME_SEED, player1.as_ref(), &nonce.to_le_bytes()], program_id)
+}
+
+/// Derives the vault authority PDA for a game account
+pub fn find_vault_authority(program_id: &Pubkey, escrow_account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, escrow_account.as_ref()], program_id)
+}
+
+/// Derives the program's market registry
+pub fn find_market_registry_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MARKET_REGISTRY_SEED], program_id)
+}
+
+/// Derives the program config
+pub fn find_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], program_id)
+}
+
+/// Derives the ProgramData account holding the program's upgrade authority
+pub fn find_program_data_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id())
+}
+
+/// Derives the mock price feed updated by `authority`
+pub fn find_mock_price_address(program_id: &Pubkey, authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MOCK_PRICE_SEED, authority.as_ref()], program_id)
+}
+
+/// Address of a game's escrow token account: the vault authority's associated token account
+pub fn get_vault_address(
+    program_id: &Pubkey,
+    escrow_account: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Pubkey {
+    let (vault_authority, _) = find_vault_authority(program_id, escrow_account);
+    get_associated_token_address_with_program_id(&vault_authority, mint, token_program)
+}
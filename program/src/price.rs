@@ -0,0 +1,142 @@
+use crate::{error::EscrowError, math::exceeds_bps};
+use solana_program::{msg, program_error::ProgramError};
+
+/// Exponent of every price stored in a game: prices are fixed-point with 8 decimals
+pub const PRICE_EXPO: i32 = -8;
+
+/// Converts an oracle price `price * 10^expo` into a fixed-point price with
+/// exponent `PRICE_EXPO`, rejecting zero and negative prices.
+///
+/// Digits below `10^PRICE_EXPO` are truncated.
+pub fn normalize_price(price: i64, expo: i32) -> Result<u64, ProgramError> {
+    if price <= 0 {
+        msg!("Oracle price {} is not positive", price);
+        return Err(EscrowError::NonPositivePrice.into());
+    }
+    let price = price as u64;
+    // Widened so that no feed exponent can overflow the difference
+    let scale_expo = u32::try_from((i64::from(expo) - i64::from(PRICE_EXPO)).unsigned_abs())
+        .map_err(|_| EscrowError::ArithmeticOverflow)?;
+    let normalized = if expo >= PRICE_EXPO {
+        10u64
+            .checked_pow(scale_expo)
+            .and_then(|scale| price.checked_mul(scale))
+            .ok_or(EscrowError::ArithmeticOverflow)?
+    } else {
+        // A scale beyond u64 leaves nothing of the price
+        10u64.checked_pow(scale_expo).map_or(0, |scale| price / scale)
+    };
+    if normalized == 0 {
+        msg!("Oracle price {}e{} is below the price precision", price, expo);
+        return Err(EscrowError::NonPositivePrice.into());
+    }
+    Ok(normalized)
+}
+
+/// Ensures `price` is within `max_drift_bps` basis points of `entry_price`,
+/// whichever way it moved.
+///
+/// The comparison is exact: a drift of exactly `max_drift_bps` is accepted and
+/// any drift above it, however small, is rejected.
+pub fn assert_join_drift(
+    entry_price: u64,
+    price: u64,
+    max_drift_bps: u16,
+) -> Result<(), ProgramError> {
+    if exceeds_bps(entry_price.abs_diff(price), entry_price, max_drift_bps) {
+        msg!(
+            "Price {} drifted more than {} bps from the entry price {}",
+            price,
+            max_drift_bps,
+            entry_price
+        );
+        return Err(EscrowError::PriceFluctuationTooHigh.into());
+    }
+    Ok(())
+}
+
+/// Returns the caller-supplied price to use instead of the oracle, if any.
+///
+/// Overrides only exist in builds with the `test-price-override` feature;
+/// everywhere else a non-zero price is rejected and prices come from the oracle.
+pub fn price_override(price: u64) -> Result<Option<u64>, ProgramError> {
+    if price == 0 {
+        Ok(None)
+    } else if cfg!(feature = "test-price-override") {
+        Ok(Some(price))
+    } else {
+        msg!("Price overrides are disabled, prices are only read from the oracle");
+        Err(EscrowError::PriceOverrideDisabled.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENTRY: u64 = 250_000_000_000; // 2500 with 8 decimals
+
+    fn too_high() -> ProgramError {
+        EscrowError::PriceFluctuationTooHigh.into()
+    }
+
+    #[test]
+    fn normalize_price_scales_to_price_expo() {
+        assert_eq!(normalize_price(2_500, 0), Ok(ENTRY));
+        assert_eq!(normalize_price(250_000_000_000, PRICE_EXPO), Ok(ENTRY));
+        assert_eq!(normalize_price(2_500_000_000_000, -9), Ok(ENTRY));
+    }
+
+    #[test]
+    fn normalize_price_rejects_extreme_exponents_without_overflow() {
+        assert_eq!(
+            normalize_price(i64::MAX, i32::MAX),
+            Err(EscrowError::ArithmeticOverflow.into())
+        );
+        assert_eq!(normalize_price(i64::MAX, i32::MIN), Err(EscrowError::NonPositivePrice.into()));
+    }
+
+    #[test]
+    fn join_drift_accepts_unchanged_price() {
+        assert_eq!(assert_join_drift(ENTRY, ENTRY, 100), Ok(()));
+    }
+
+    #[test]
+    fn join_drift_accepts_rise_and_drop_within_limit() {
+        assert_eq!(assert_join_drift(ENTRY, ENTRY + ENTRY / 200, 100), Ok(()));
+        assert_eq!(assert_join_drift(ENTRY, ENTRY - ENTRY / 200, 100), Ok(()));
+    }
+
+    #[test]
+    fn join_drift_accepts_exact_boundary_both_ways() {
+        assert_eq!(assert_join_drift(ENTRY, ENTRY + ENTRY / 100, 100), Ok(()));
+        assert_eq!(assert_join_drift(ENTRY, ENTRY - ENTRY / 100, 100), Ok(()));
+    }
+
+    #[test]
+    fn join_drift_rejects_one_unit_past_boundary_both_ways() {
+        assert_eq!(assert_join_drift(ENTRY, ENTRY + ENTRY / 100 + 1, 100), Err(too_high()));
+        assert_eq!(assert_join_drift(ENTRY, ENTRY - ENTRY / 100 - 1, 100), Err(too_high()));
+    }
+
+    #[test]
+    fn join_drift_rejects_drift_below_next_whole_percent() {
+        // 1.99% used to pass a 1% limit through integer division
+        let drift = ENTRY * 199 / 10_000;
+        assert_eq!(assert_join_drift(ENTRY, ENTRY + drift, 100), Err(too_high()));
+        assert_eq!(assert_join_drift(ENTRY, ENTRY - drift, 100), Err(too_high()));
+    }
+
+    #[test]
+    fn join_drift_rejects_large_drop_without_underflow() {
+        assert_eq!(assert_join_drift(ENTRY, 1, 100), Err(too_high()));
+        assert_eq!(assert_join_drift(u64::MAX, 0, 1_000), Err(too_high()));
+    }
+
+    #[test]
+    fn join_drift_handles_extreme_prices() {
+        assert_eq!(assert_join_drift(u64::MAX, u64::MAX, 1), Ok(()));
+        assert_eq!(assert_join_drift(u64::MAX, u64::MAX - u64::MAX / 10_000, 1), Ok(()));
+        assert_eq!(assert_join_drift(1, u64::MAX, 10_000), Err(too_high()));
+    }
+}
@@ -1,11 +1,19 @@
+#[cfg(not(feature = "mock-oracle"))]
+use crate::error::EscrowError;
+use crate::instruction::EscrowInstruction;
 use crate::instructions::{
     create_game::create_game, fetch_price::fetch_price, join_game::join_game,
     settle_game::settle_game, withdraw_funds::withdraw_funds, close_game::close_game,
+    refund_draw::refund_draw, refund_unjoined::refund_unjoined,
+    initialize_market_registry::initialize_market_registry, add_market::add_market,
+    update_market::update_market, initialize_config::initialize_config,
+    update_config::update_config, set_paused::set_paused, propose_admin::propose_admin,
+    accept_admin::accept_admin, apply_config_update::apply_config_update,
+    apply_market_update::apply_market_update, cancel_pending_updates::cancel_pending_updates,
 };
-use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
-    pubkey::Pubkey,
-};
+#[cfg(feature = "mock-oracle")]
+use crate::instructions::set_mock_price::set_mock_price;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey};
 
 pub struct Processor;
 
@@ -15,19 +23,136 @@ impl Processor {
         accounts: &[AccountInfo],
         instruction_data: &[u8],
     ) -> ProgramResult {
-        let instruction = instruction_data[0];
+        let instruction = EscrowInstruction::unpack(instruction_data)?;
 
         msg!("Instruction data length: {}", instruction_data.len());
         msg!(" Instruction: {:?}", instruction);
 
         match instruction {
-            0 => create_game(program_id, accounts, instruction_data),// Create the game
-            1 => fetch_price(program_id, accounts),// fetch_price
-            2 => join_game(program_id, accounts, instruction_data),// Player joins
-            3 => settle_game(program_id, accounts, instruction_data),// Settle the game 
-            4 => withdraw_funds(program_id, accounts),//
-            5 => close_game(accounts),//  Distribute winnings
-            _ => Err(ProgramError::InvalidInstructionData),
+            EscrowInstruction::CreateGame {
+                choice,
+                entry_price,
+                nonce,
+                stake,
+                duration,
+                join_window,
+                max_price_age,
+                max_confidence_bps,
+                market_id,
+                max_join_drift_bps,
+                win_threshold_bps,
+            } => create_game(
+                program_id,
+                accounts,
+                choice,
+                entry_price,
+                nonce,
+                stake,
+                duration,
+                join_window,
+                max_price_age,
+                max_confidence_bps,
+                market_id,
+                max_join_drift_bps,
+                win_threshold_bps,
+            ), // Create the game
+            EscrowInstruction::FetchPrice => fetch_price(program_id, accounts),
+            EscrowInstruction::JoinGame { last_price, stake } => {
+                join_game(program_id, accounts, last_price, stake) // Player joins
+            }
+            EscrowInstruction::SettleGame { last_price } => {
+                settle_game(program_id, accounts, last_price) // Settle the game
+            }
+            EscrowInstruction::WithdrawFunds => withdraw_funds(program_id, accounts),
+            EscrowInstruction::CloseGame => close_game(program_id, accounts), // Distribute winnings
+            EscrowInstruction::RefundDraw => refund_draw(program_id, accounts), // Refund an expired game
+            EscrowInstruction::RefundUnjoined => refund_unjoined(program_id, accounts), // Refund a game nobody joined
+            #[cfg(feature = "mock-oracle")]
+            EscrowInstruction::SetMockPrice { price, conf, expo } => {
+                set_mock_price(program_id, accounts, price, conf, expo) // Test-only price feed
+            }
+            #[cfg(not(feature = "mock-oracle"))]
+            EscrowInstruction::SetMockPrice { .. } => {
+                msg!("Mock price feeds are not available in this build");
+                Err(EscrowError::InvalidInstruction.into())
+            }
+            EscrowInstruction::InitializeMarketRegistry => {
+                initialize_market_registry(program_id, accounts) // Create the market registry
+            }
+            EscrowInstruction::AddMarket {
+                symbol,
+                oracle_type,
+                max_join_drift_bps,
+                win_threshold_bps,
+            } => add_market(
+                program_id,
+                accounts,
+                symbol,
+                oracle_type,
+                max_join_drift_bps,
+                win_threshold_bps,
+            ), // Register a market
+            EscrowInstruction::UpdateMarket {
+                market_id,
+                max_join_drift_bps,
+                win_threshold_bps,
+                active,
+            } => update_market(
+                program_id,
+                accounts,
+                market_id,
+                max_join_drift_bps,
+                win_threshold_bps,
+                active,
+            ), // Change or retire a market
+            EscrowInstruction::InitializeConfig {
+                default_max_join_drift_bps,
+                default_win_threshold_bps,
+                fee_bps,
+                treasury,
+                timelock_delay,
+            } => initialize_config(
+                program_id,
+                accounts,
+                default_max_join_drift_bps,
+                default_win_threshold_bps,
+                fee_bps,
+                treasury,
+                timelock_delay,
+            ), // Create the program config
+            EscrowInstruction::UpdateConfig {
+                allowed_mints,
+                default_max_join_drift_bps,
+                default_win_threshold_bps,
+                fee_bps,
+                treasury,
+                timelock_delay,
+            } => update_config(
+                program_id,
+                accounts,
+                allowed_mints,
+                default_max_join_drift_bps,
+                default_win_threshold_bps,
+                fee_bps,
+                treasury,
+                timelock_delay,
+            ), // Queue a change of the program config
+            EscrowInstruction::SetPaused { paused } => {
+                set_paused(program_id, accounts, paused) // Halt or resume games
+            }
+            EscrowInstruction::ProposeAdmin { new_admin } => {
+                propose_admin(program_id, accounts, new_admin) // Nominate the next admin
+            }
+            EscrowInstruction::AcceptAdmin => accept_admin(program_id, accounts), // Take over as admin
+            EscrowInstruction::ApplyConfigUpdate => {
+                apply_config_update(program_id, accounts) // Put queued settings in force
+            }
+            EscrowInstruction::ApplyMarketUpdate => {
+                apply_market_update(program_id, accounts) // Put a queued market in force
+            }
+            EscrowInstruction::CancelPendingUpdates => {
+                cancel_pending_updates(program_id, accounts) // Drop queued updates
+            }
         }
     }
 }
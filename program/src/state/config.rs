@@ -0,0 +1,77 @@
+use crate::error::EscrowError;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{entrypoint::ProgramResult, msg, pubkey::Pubkey};
+
+/// Most mints the config can allow; its account is sized for all of them up front
+pub const MAX_ALLOWED_MINTS: usize = 8;
+
+/// Largest protocol fee the admin can set, in basis points of the payout
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// Longest delay the admin can put on queued updates, 30 days
+pub const MAX_TIMELOCK_DELAY: i64 = 30 * 24 * 60 * 60;
+
+/// Settings the admin can only change through the timelock
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConfigSettings {
+    pub default_max_join_drift_bps: u16, // Join drift of markets registered without one
+    pub default_win_threshold_bps: u16, // Win threshold of markets registered without one
+    pub fee_bps: u16, // Protocol fee taken from payouts, in basis points
+    pub treasury: Pubkey, // Owner of the token accounts receiving the protocol fee
+    pub allowed_mint_count: u8, // Number of entries of `allowed_mints` in use, 0 to allow any mint
+    pub allowed_mints: [Pubkey; MAX_ALLOWED_MINTS], // Mints games can be staked in
+    pub timelock_delay: i64, // Seconds a queued update waits before it can be applied
+}
+
+impl ConfigSettings {
+    /// Whether games can be staked in `mint`
+    pub fn is_mint_allowed(&self, mint: &Pubkey) -> bool {
+        self.allowed_mint_count == 0
+            || self.allowed_mints[..self.allowed_mint_count as usize].contains(mint)
+    }
+
+    /// Replaces the allowed mints, an empty list allowing any mint
+    pub fn set_allowed_mints(&mut self, mints: &[Pubkey]) -> ProgramResult {
+        if mints.len() > MAX_ALLOWED_MINTS {
+            msg!("At most {} mints can be allowed, got {}", MAX_ALLOWED_MINTS, mints.len());
+            return Err(EscrowError::InvalidConfigParameters.into());
+        }
+        self.allowed_mints = [Pubkey::default(); MAX_ALLOWED_MINTS];
+        self.allowed_mints[..mints.len()].copy_from_slice(mints);
+        self.allowed_mint_count = mints.len() as u8;
+        Ok(())
+    }
+}
+
+/// Program-wide settings, at the PDA `[b"config"]`
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct Config {
+    pub admin: Pubkey, // Only signer allowed to update the config, the markets or pause the program
+    pub bump: u8, // Bump of the config PDA
+    pub paused: bool, // While set, games can only be refunded
+    pub settings: ConfigSettings, // Settings in force
+    pub pending_admin: Pubkey, // Admin proposed by the current one, default while none is
+    pub pending_settings: ConfigSettings, // Settings queued by the admin
+    pub pending_settings_at: i64, // Time from which the queued settings can be applied
+    pub has_pending_settings: bool, // Whether `pending_settings` holds a queued update
+}
+
+impl Config {
+    /// Queues `settings` to replace the ones in force from `effective_at`,
+    /// replacing any update already queued
+    pub fn queue_settings(&mut self, settings: ConfigSettings, effective_at: i64) {
+        if self.has_pending_settings {
+            msg!("Replacing the settings queued for {}", self.pending_settings_at);
+        }
+        self.pending_settings = settings;
+        self.pending_settings_at = effective_at;
+        self.has_pending_settings = true;
+    }
+
+    /// Forgets the queued settings
+    pub fn clear_pending_settings(&mut self) {
+        self.pending_settings = ConfigSettings::default();
+        self.pending_settings_at = 0;
+        self.has_pending_settings = false;
+    }
+}
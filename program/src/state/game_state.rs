@@ -1,5 +1,15 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::pubkey::{Pubkey};
+use crate::{math::checked_add, oracle::OracleType, state::game_status::GameStatus};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+/// Smallest win threshold a game or market can use, in basis points
+pub const MIN_WIN_THRESHOLD_BPS: u16 = 50;
+
+/// Largest win threshold a game or market can use, in basis points
+pub const MAX_WIN_THRESHOLD_BPS: u16 = 5_000;
+
+/// Largest join drift a game or market can use, in basis points
+pub const MAX_JOIN_DRIFT_BPS: u16 = 1_000;
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
 pub struct GameState {
@@ -9,6 +19,36 @@ pub struct GameState {
     pub player2_choice: bool, // Player 2's bet: true for increase, false for decrease
     pub entry_price: u64, 
     pub last_price: u64, // 8 bytes
-    pub game_active: bool,// 1 byte (0 or 1 to represent true/false)
+    pub status: GameStatus, // Stage of the game's lifecycle
     pub winner: Pubkey,// Pubkey of the winner
+    pub escrow_token_account: Pubkey, // Token account holding both stakes
+    pub vault_bump: u8, // Bump of the PDA owning the escrow token account
+    pub nonce: u64, // Client-chosen seed of the game account
+    pub bump: u8, // Bump of the game account PDA
+    pub stake: u64, // Amount each player puts in the vault, in the mint's smallest units
+    pub mint: Pubkey, // Mint of the staked token
+    pub deposited: u64, // Amount that landed in the vault, net of any Token-2022 transfer fees
+    pub expires_at: i64, // Unix timestamp after which an unsettled game can be refunded as a draw
+    pub join_deadline: i64, // Unix timestamp from which Player 2 can no longer join and Player 1 can be refunded
+    pub max_price_age: u64, // Oldest oracle price accepted, in seconds
+    pub max_confidence_bps: u16, // Widest oracle confidence interval accepted, in basis points of the price
+    pub price_expo: i32, // Exponent of entry_price and last_price, always PRICE_EXPO
+    pub oracle: Pubkey, // Price feed account pinned at creation, the only one prices are read from
+    pub oracle_program: Pubkey, // Program owning the pinned price feed account
+    pub oracle_type: OracleType, // Kind of feed the pinned account is, selecting how prices are read
+    pub market_id: u8, // Registry id of the market the game is played on
+    pub max_join_drift_bps: u16, // Largest move from entry_price at which Player 2 can still join, set at creation
+    pub win_threshold_bps: u16, // Move from entry_price that decides the game, set at creation
+    pub fee_bps: u16, // Protocol fee taken from the payout, copied from the config at creation
+}
+
+/// Closes a finished game's account, zeroing its data so it can no longer be
+/// read as a game and returning its rent to `player1_account`, who paid for it
+pub fn close_game_account(escrow_account: &AccountInfo, player1_account: &AccountInfo) -> ProgramResult {
+    let rent = escrow_account.lamports();
+    let player1_lamports = checked_add(player1_account.lamports(), rent)?;
+    **player1_account.try_borrow_mut_lamports()? = player1_lamports;
+    **escrow_account.try_borrow_mut_lamports()? = 0;
+    escrow_account.try_borrow_mut_data()?.fill(0);
+    Ok(())
 }
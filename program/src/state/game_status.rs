@@ -0,0 +1,118 @@
+use crate::error::EscrowError;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{entrypoint::ProgramResult, msg};
+
+/// Stage of a game's lifecycle.
+///
+/// ```text
+/// Open ──join_game──> Matched ──settle_game──> Settled ──close_game──> PaidOut
+///  │                     │
+///  │                     └──refund_draw──> Drawn
+///  ├──withdraw_funds──> Cancelled
+///  └──refund_unjoined──> Expired
+/// ```
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameStatus {
+    /// Player 1 staked and the game is waiting for Player 2
+    #[default]
+    Open,
+    /// Both players staked and the game is waiting for a winning price move
+    Matched,
+    /// The price moved enough to decide a winner, who has not been paid yet
+    Settled,
+    /// The winner was paid the vault
+    PaidOut,
+    /// Player 1 withdrew their stake before anyone joined
+    Cancelled,
+    /// Nobody joined before the join deadline and Player 1 was refunded
+    Expired,
+    /// The game expired without a winner and both players were refunded
+    Drawn,
+}
+
+impl GameStatus {
+    /// Whether a game in this status can move to `next`
+    pub fn can_transition_to(self, next: GameStatus) -> bool {
+        use GameStatus::*;
+        matches!(
+            (self, next),
+            (Open, Matched)
+                | (Open, Cancelled)
+                | (Open, Expired)
+                | (Matched, Settled)
+                | (Matched, Drawn)
+                | (Settled, PaidOut)
+        )
+    }
+
+    /// Ensures a game in this status can move to `next`
+    pub fn assert_transition(self, next: GameStatus) -> ProgramResult {
+        if !self.can_transition_to(next) {
+            msg!("Game is {:?} and cannot become {:?}", self, next);
+            return Err(EscrowError::InvalidGameStatus.into());
+        }
+        Ok(())
+    }
+
+    /// Moves the game to `next` if its current status allows it
+    pub fn transition_to(&mut self, next: GameStatus) -> ProgramResult {
+        self.assert_transition(next)?;
+        *self = next;
+        Ok(())
+    }
+
+    /// Whether the game still reads prices, i.e. it has not been decided or refunded
+    pub fn is_in_play(self) -> bool {
+        matches!(self, GameStatus::Open | GameStatus::Matched)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use GameStatus::*;
+
+    const ALL: [GameStatus; 7] = [Open, Matched, Settled, PaidOut, Cancelled, Expired, Drawn];
+
+    #[test]
+    fn transition_table() {
+        // Rows are the current status, columns the next one, in the order of `ALL`
+        let table = [
+            // Open, Matched, Settled, PaidOut, Cancelled, Expired, Drawn
+            [false, true, false, false, true, true, false], // Open
+            [false, false, true, false, false, false, true], // Matched
+            [false, false, false, true, false, false, false], // Settled
+            [false, false, false, false, false, false, false], // PaidOut
+            [false, false, false, false, false, false, false], // Cancelled
+            [false, false, false, false, false, false, false], // Expired
+            [false, false, false, false, false, false, false], // Drawn
+        ];
+        for (from, row) in ALL.iter().zip(table) {
+            for (to, allowed) in ALL.iter().zip(row) {
+                assert_eq!(from.can_transition_to(*to), allowed, "{:?} -> {:?}", from, to);
+
+                let mut status = *from;
+                let result = status.transition_to(*to);
+                if allowed {
+                    assert_eq!(result, Ok(()));
+                    assert_eq!(status, *to);
+                } else {
+                    assert_eq!(result, Err(EscrowError::InvalidGameStatus.into()));
+                    assert_eq!(status, *from);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn only_open_and_matched_games_are_in_play() {
+        for status in ALL {
+            assert_eq!(status.is_in_play(), matches!(status, Open | Matched), "{:?}", status);
+        }
+    }
+
+    #[test]
+    fn new_games_are_open() {
+        assert_eq!(GameStatus::default(), Open);
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{error::EscrowError, oracle::OracleType};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{msg, program_error::ProgramError, pubkey::Pubkey};
+
+/// Most markets a registry can hold; its account is sized for all of them up front
+pub const MAX_MARKETS: usize = 16;
+
+/// A price pair games can be played on
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Market {
+    pub symbol: [u8; 8], // Asset symbol, e.g. "ETH", zero-padded
+    pub oracle: Pubkey, // Approved price feed of the asset
+    pub oracle_type: OracleType, // Kind of feed `oracle` is
+    pub max_join_drift_bps: u16, // Largest price move allowed before Player 2 joins, in basis points
+    pub win_threshold_bps: u16, // Price move that decides the winner, in basis points
+    pub active: bool, // New games can only be created on active markets
+}
+
+impl Market {
+    /// Symbol without its zero padding, for logs
+    pub fn symbol(&self) -> &str {
+        let len = self.symbol.iter().position(|&b| b == 0).unwrap_or(self.symbol.len());
+        std::str::from_utf8(&self.symbol[..len]).unwrap_or("?")
+    }
+}
+
+/// Markets approved for games, at the PDA `[b"markets"]`. The config's admin
+/// manages them, and changes wait out the config's timelock.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct MarketRegistry {
+    pub bump: u8, // Bump of the registry PDA
+    pub market_count: u8, // Number of entries of `markets` in use
+    pub markets: [Market; MAX_MARKETS], // Markets by id, only the first `market_count` are set
+    pub pending_market_id: u8, // Id the queued market is written to, `market_count` for a new one
+    pub pending_market: Market, // Market queued by the admin
+    pub pending_market_at: i64, // Time from which the queued market can be applied
+    pub has_pending_market: bool, // Whether `pending_market` holds a queued change
+}
+
+impl MarketRegistry {
+    /// Returns the market registered under `market_id`
+    pub fn market(&self, market_id: u8) -> Result<&Market, ProgramError> {
+        if market_id >= self.market_count {
+            msg!("Market {} does not exist", market_id);
+            return Err(EscrowError::InvalidMarket.into());
+        }
+        Ok(&self.markets[market_id as usize])
+    }
+
+    /// Queues `market` to be written under `market_id` from `effective_at`,
+    /// replacing any change already queued
+    pub fn queue_market(&mut self, market_id: u8, market: Market, effective_at: i64) {
+        if self.has_pending_market {
+            msg!("Replacing the change queued for market {}", self.pending_market_id);
+        }
+        self.pending_market_id = market_id;
+        self.pending_market = market;
+        self.pending_market_at = effective_at;
+        self.has_pending_market = true;
+    }
+
+    /// Forgets the queued market change
+    pub fn clear_pending_market(&mut self) {
+        self.pending_market_id = 0;
+        self.pending_market = Market::default();
+        self.pending_market_at = 0;
+        self.has_pending_market = false;
+    }
+}
@@ -0,0 +1,12 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+/// Price feed owned by this program, standing in for Pyth in builds with the `mock-oracle` feature
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct MockPrice {
+    pub authority: Pubkey, // Only signer allowed to update the price
+    pub price: i64, // Price mantissa, as in a Pyth feed
+    pub conf: u64, // Confidence interval, with the same exponent as the price
+    pub expo: i32, // Exponent of price and conf
+    pub publish_time: i64, // Unix timestamp of the last update
+}
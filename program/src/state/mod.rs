@@ -1 +1,5 @@
+pub mod config;
 pub mod game_state;
+pub mod game_status;
+pub mod market_registry;
+pub mod mock_price;
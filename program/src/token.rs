@@ -0,0 +1,153 @@
+use crate::{error::EscrowError, math::checked_sub};
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg,
+    program::{invoke, invoke_signed}, program_error::ProgramError, sysvar::Sysvar,
+};
+use spl_token_2022::{
+    extension::{
+        transfer_fee::{
+            instruction::{harvest_withheld_tokens_to_mint, transfer_checked_with_fee},
+            TransferFeeAmount, TransferFeeConfig,
+        },
+        BaseStateWithExtensions, StateWithExtensions,
+    },
+    instruction::{close_account, transfer_checked},
+    state::{Account as TokenAccount, Mint},
+};
+
+/// Unpacks an SPL Token or Token-2022 account, ignoring any extensions
+pub fn unpack_token_account(token_account: &AccountInfo) -> Result<TokenAccount, ProgramError> {
+    let data = token_account.try_borrow_data()?;
+    Ok(StateWithExtensions::<TokenAccount>::unpack(&data)?.base)
+}
+
+/// Unpacks an SPL Token or Token-2022 mint, ignoring any extensions
+pub fn unpack_mint(mint: &AccountInfo) -> Result<Mint, ProgramError> {
+    let data = mint.try_borrow_data()?;
+    Ok(StateWithExtensions::<Mint>::unpack(&data)?.base)
+}
+
+/// Returns the fee the mint withholds when `amount` is transferred in the
+/// current epoch, or `None` if the mint has no transfer-fee extension
+pub fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<Option<u64>, ProgramError> {
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<Mint>::unpack(&data)?;
+    let Ok(fee_config) = mint_state.get_extension::<TransferFeeConfig>() else {
+        return Ok(None);
+    };
+    let epoch = Clock::get()?.epoch;
+    fee_config
+        .calculate_epoch_fee(epoch, amount)
+        .map(Some)
+        .ok_or_else(|| EscrowError::ArithmeticOverflow.into())
+}
+
+/// Moves `amount` tokens with `transfer_checked`, asserting the transfer fee
+/// for Token-2022 mints that charge one, and returns what the destination received.
+///
+/// `signer_seeds` is empty when `authority` signed the transaction itself.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer<'a>(
+    token_program: &AccountInfo<'a>,
+    source: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    amount: u64,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64, ProgramError> {
+    let fee = transfer_fee(mint, amount)?;
+    let instruction = match fee {
+        Some(fee) => transfer_checked_with_fee(
+            token_program.key,
+            source.key,
+            mint.key,
+            destination.key,
+            authority.key,
+            &[],
+            amount,
+            decimals,
+            fee,
+        )?,
+        None => transfer_checked(
+            token_program.key,
+            source.key,
+            mint.key,
+            destination.key,
+            authority.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+    };
+    invoke_signed(
+        &instruction,
+        &[
+            source.clone(),
+            mint.clone(),
+            destination.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    checked_sub(amount, fee.unwrap_or(0))
+}
+
+/// Closes a vault, sending its rent to `destination`. Tokens still in the
+/// vault, e.g. sent to it by a third party, are swept to `sweep_destination`
+/// first, so the vault always closes with its game.
+///
+/// Fees a Token-2022 mint withheld in the vault are harvested to the mint
+/// first, since an account still holding them cannot be closed.
+#[allow(clippy::too_many_arguments)]
+pub fn close_vault<'a>(
+    token_program: &AccountInfo<'a>,
+    vault: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    sweep_destination: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let (amount, withheld) = {
+        let data = vault.try_borrow_data()?;
+        let vault_state = StateWithExtensions::<TokenAccount>::unpack(&data)?;
+        let withheld = vault_state
+            .get_extension::<TransferFeeAmount>()
+            .map(|fee_amount| u64::from(fee_amount.withheld_amount))
+            .unwrap_or(0);
+        (vault_state.base.amount, withheld)
+    };
+    if amount > 0 {
+        msg!("Vault {} still holds {}, sweeping it to {}", vault.key, amount, sweep_destination.key);
+        transfer(
+            token_program,
+            vault,
+            mint,
+            sweep_destination,
+            authority,
+            amount,
+            decimals,
+            signer_seeds,
+        )?;
+    }
+    if withheld > 0 {
+        invoke(
+            &harvest_withheld_tokens_to_mint(token_program.key, mint.key, &[vault.key])?,
+            &[mint.clone(), vault.clone(), token_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &close_account(token_program.key, vault.key, destination.key, authority.key, &[])?,
+        &[
+            vault.clone(),
+            destination.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )
+}
@@ -0,0 +1,307 @@
+use crate::{
+    error::EscrowError,
+    pda::{find_program_data_address, CONFIG_SEED, GAME_SEED, MARKET_REGISTRY_SEED, VAULT_SEED},
+    state::{
+        config::{Config, ConfigSettings, MAX_FEE_BPS, MAX_TIMELOCK_DELAY},
+        game_state::{GameState, MAX_JOIN_DRIFT_BPS, MAX_WIN_THRESHOLD_BPS, MIN_WIN_THRESHOLD_BPS},
+        market_registry::MarketRegistry,
+    },
+    token::{unpack_mint, unpack_token_account},
+};
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::AccountInfo, bpf_loader_upgradeable, clock::Clock, entrypoint::ProgramResult,
+    msg, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
+};
+use spl_token_2022::{
+    check_spl_token_program_account,
+    state::{Account as TokenAccount, Mint},
+};
+
+/// Ensures the account signed the transaction
+pub fn assert_signer(account: &AccountInfo) -> ProgramResult {
+    if !account.is_signer {
+        msg!("Missing required signature for {}", account.key);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+/// Ensures the account is owned by the given program
+pub fn assert_owned_by(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if account.owner != owner {
+        msg!("Account {} is not owned by {}", account.key, owner);
+        return Err(ProgramError::IllegalOwner);
+    }
+    Ok(())
+}
+
+/// Ensures the signer is the program's upgrade authority, so that accounts set
+/// up once per program cannot be claimed by whoever sends the instruction first
+pub fn assert_upgrade_authority(
+    authority: &AccountInfo,
+    program_data: &AccountInfo,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    assert_signer(authority)?;
+    let (expected, _) = find_program_data_address(program_id);
+    if *program_data.key != expected || *program_data.owner != bpf_loader_upgradeable::id() {
+        msg!("Account {} is not the ProgramData account of {}", program_data.key, program_id);
+        return Err(EscrowError::InvalidProgramData.into());
+    }
+
+    // `UpgradeableLoaderState::ProgramData` is bincode-encoded: a u32 variant
+    // index of 3, the u64 deployment slot, then an optional upgrade authority
+    let data = program_data.try_borrow_data()?;
+    if data.len() < 45 || data[..4] != 3u32.to_le_bytes() {
+        msg!("Account {} does not hold program data", program_data.key);
+        return Err(EscrowError::InvalidProgramData.into());
+    }
+    if data[12] != 1 || data[13..45] != authority.key.to_bytes() {
+        msg!("Signer {} is not the upgrade authority of {}", authority.key, program_id);
+        return Err(EscrowError::Unauthorized.into());
+    }
+    Ok(())
+}
+
+/// Ensures the token account's authority is `owner` and returns its unpacked state
+pub fn assert_token_owner(
+    token_account: &AccountInfo,
+    owner: &Pubkey,
+) -> Result<TokenAccount, ProgramError> {
+    let token_account_data = unpack_token_account(token_account)?;
+    if token_account_data.owner != *owner {
+        msg!(
+            "Token account {} is owned by {}, expected {}",
+            token_account.key,
+            token_account_data.owner,
+            owner
+        );
+        return Err(EscrowError::TokenAccountOwnerMismatch.into());
+    }
+    Ok(token_account_data)
+}
+
+/// Ensures the token account holds the game's mint
+pub fn assert_token_mint(token_account: &AccountInfo, mint: &Pubkey) -> ProgramResult {
+    let token_account_data = unpack_token_account(token_account)?;
+    if token_account_data.mint != *mint {
+        msg!(
+            "Token account {} holds mint {}, expected {}",
+            token_account.key,
+            token_account_data.mint,
+            mint
+        );
+        return Err(EscrowError::MintMismatch.into());
+    }
+    Ok(())
+}
+
+/// Ensures the mint account is the game's mint and returns its unpacked state
+pub fn assert_mint(mint: &AccountInfo, expected: &Pubkey) -> Result<Mint, ProgramError> {
+    if mint.key != expected {
+        msg!("Mint {} is not the stake mint {}", mint.key, expected);
+        return Err(EscrowError::MintMismatch.into());
+    }
+    unpack_mint(mint)
+}
+
+/// Ensures the token program is SPL Token or Token-2022 and is the one owning the mint
+pub fn assert_token_program(token_program: &AccountInfo, mint: &AccountInfo) -> ProgramResult {
+    if check_spl_token_program_account(token_program.key).is_err() || mint.owner != token_program.key {
+        msg!("Token program {} does not own mint {}", token_program.key, mint.key);
+        return Err(EscrowError::InvalidTokenProgram.into());
+    }
+    Ok(())
+}
+
+/// Ensures the game account is the PDA derived from its recorded Player 1 and nonce
+pub fn assert_game_address(
+    escrow_account: &AccountInfo,
+    program_id: &Pubkey,
+    game_state: &GameState,
+) -> ProgramResult {
+    let expected = Pubkey::create_program_address(
+        &[
+            GAME_SEED,
+            game_state.player1.as_ref(),
+            &game_state.nonce.to_le_bytes(),
+            &[game_state.bump],
+        ],
+        program_id,
+    )?;
+    if *escrow_account.key != expected {
+        msg!("Game account {} is not derived from its Player 1 and nonce", escrow_account.key);
+        return Err(EscrowError::InvalidGameAccount.into());
+    }
+    Ok(())
+}
+
+/// Ensures the account receiving a finished game's rent is Player 1, who paid for it
+pub fn assert_rent_recipient(player1_account: &AccountInfo, game_state: &GameState) -> ProgramResult {
+    if *player1_account.key != game_state.player1 {
+        msg!("Rent of the game goes back to Player 1 {}, not {}", game_state.player1, player1_account.key);
+        return Err(EscrowError::RentRecipientMismatch.into());
+    }
+    Ok(())
+}
+
+/// Ensures the vault authority is the game's PDA for the recorded bump
+pub fn assert_vault_authority(
+    vault_authority: &AccountInfo,
+    program_id: &Pubkey,
+    escrow_account: &Pubkey,
+    vault_bump: u8,
+) -> ProgramResult {
+    let expected =
+        Pubkey::create_program_address(&[VAULT_SEED, escrow_account.as_ref(), &[vault_bump]], program_id)?;
+    if *vault_authority.key != expected {
+        msg!("Vault authority {} does not belong to this game", vault_authority.key);
+        return Err(EscrowError::InvalidVaultAuthority.into());
+    }
+    Ok(())
+}
+
+/// Ensures the oracle account is the price feed pinned for the game, owned by the same program
+pub fn assert_oracle(oracle_account: &AccountInfo, game_state: &GameState) -> ProgramResult {
+    if *oracle_account.key != game_state.oracle || *oracle_account.owner != game_state.oracle_program {
+        msg!("Oracle account {} is not the feed {} pinned for this game", oracle_account.key, game_state.oracle);
+        return Err(EscrowError::OracleMismatch.into());
+    }
+    Ok(())
+}
+
+/// Ensures the account is the program's market registry and returns its state
+pub fn assert_market_registry(
+    registry_account: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<MarketRegistry, ProgramError> {
+    assert_owned_by(registry_account, program_id)?;
+    let registry = MarketRegistry::try_from_slice(&registry_account.try_borrow_data()?)?;
+    let expected =
+        Pubkey::create_program_address(&[MARKET_REGISTRY_SEED, &[registry.bump]], program_id)?;
+    if *registry_account.key != expected {
+        msg!("Market registry {} is not the program's registry", registry_account.key);
+        return Err(EscrowError::InvalidMarketRegistry.into());
+    }
+    Ok(registry)
+}
+
+/// Ensures the account is the program's config and returns its state
+pub fn assert_config(
+    config_account: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<Config, ProgramError> {
+    assert_owned_by(config_account, program_id)?;
+    let config = Config::try_from_slice(&config_account.try_borrow_data()?)?;
+    let expected = Pubkey::create_program_address(&[CONFIG_SEED, &[config.bump]], program_id)?;
+    if *config_account.key != expected {
+        msg!("Config {} is not the program's config", config_account.key);
+        return Err(EscrowError::InvalidConfig.into());
+    }
+    Ok(config)
+}
+
+/// Ensures the program is not paused, returning its config
+pub fn assert_not_paused(
+    config_account: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<Config, ProgramError> {
+    let config = assert_config(config_account, program_id)?;
+    if config.paused {
+        msg!("The program is paused, games can only be refunded");
+        return Err(EscrowError::ProgramPaused.into());
+    }
+    Ok(config)
+}
+
+/// Ensures the config's default thresholds, fee, allowed mints and timelock
+/// delay are within the program-wide bounds
+pub fn assert_config_settings(settings: &ConfigSettings) -> ProgramResult {
+    if !thresholds_within_bounds(
+        settings.default_max_join_drift_bps,
+        settings.default_win_threshold_bps,
+    ) {
+        return Err(EscrowError::InvalidConfigParameters.into());
+    }
+    if settings.fee_bps > MAX_FEE_BPS {
+        msg!("Fee {} bps is above the maximum of {} bps", settings.fee_bps, MAX_FEE_BPS);
+        return Err(EscrowError::InvalidConfigParameters.into());
+    }
+    if !(0..=MAX_TIMELOCK_DELAY).contains(&settings.timelock_delay) {
+        msg!(
+            "Timelock delay {} must be 0 to {} seconds",
+            settings.timelock_delay,
+            MAX_TIMELOCK_DELAY
+        );
+        return Err(EscrowError::InvalidConfigParameters.into());
+    }
+    Ok(())
+}
+
+/// Ensures the signer is the config's admin
+pub fn assert_admin(admin: &AccountInfo, config: &Config) -> ProgramResult {
+    assert_signer(admin)?;
+    if *admin.key != config.admin {
+        msg!("Signer {} is not the admin {}", admin.key, config.admin);
+        return Err(EscrowError::Unauthorized.into());
+    }
+    Ok(())
+}
+
+/// Ensures a queued update can be applied, its timelock having elapsed
+pub fn assert_timelock_elapsed(effective_at: i64) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
+    if current_time < effective_at {
+        msg!("The queued update can only be applied from {}, it is {}", effective_at, current_time);
+        return Err(EscrowError::TimelockNotElapsed.into());
+    }
+    Ok(())
+}
+
+/// Whether a join drift and win threshold are within the program-wide bounds,
+/// with the drift positive and below the threshold
+fn thresholds_within_bounds(max_join_drift_bps: u16, win_threshold_bps: u16) -> bool {
+    let within_bounds = (1..=MAX_JOIN_DRIFT_BPS).contains(&max_join_drift_bps)
+        && (MIN_WIN_THRESHOLD_BPS..=MAX_WIN_THRESHOLD_BPS).contains(&win_threshold_bps)
+        && max_join_drift_bps < win_threshold_bps;
+    if !within_bounds {
+        msg!(
+            "Join drift {} bps must be 1 to {} and below the win threshold {} bps, which must be {} to {}",
+            max_join_drift_bps,
+            MAX_JOIN_DRIFT_BPS,
+            win_threshold_bps,
+            MIN_WIN_THRESHOLD_BPS,
+            MAX_WIN_THRESHOLD_BPS
+        );
+    }
+    within_bounds
+}
+
+/// Ensures a market's default join drift and win threshold are within the program-wide bounds
+pub fn assert_market_parameters(max_join_drift_bps: u16, win_threshold_bps: u16) -> ProgramResult {
+    if !thresholds_within_bounds(max_join_drift_bps, win_threshold_bps) {
+        return Err(EscrowError::InvalidMarketParameters.into());
+    }
+    Ok(())
+}
+
+/// Ensures a game's join drift and win threshold are within the program-wide bounds
+pub fn assert_game_thresholds(max_join_drift_bps: u16, win_threshold_bps: u16) -> ProgramResult {
+    if !thresholds_within_bounds(max_join_drift_bps, win_threshold_bps) {
+        return Err(EscrowError::InvalidGameThresholds.into());
+    }
+    Ok(())
+}
+
+/// Ensures the escrow token account passed is the one recorded for the game
+pub fn assert_escrow_token_account(
+    escrow_token_account: &AccountInfo,
+    expected: &Pubkey,
+) -> ProgramResult {
+    if escrow_token_account.key != expected {
+        msg!("Escrow token account {} does not belong to this game", escrow_token_account.key);
+        return Err(EscrowError::EscrowTokenAccountMismatch.into());
+    }
+    Ok(())
+}
@@ -8,6 +8,7 @@ import {
     TransactionInstruction,
 } from '@solana/web3.js';
 
+import { getAssociatedTokenAddressSync } from '@solana/spl-token';
 import { readFileSync } from 'fs';
 
 
@@ -24,20 +25,29 @@ function createKeypairFromFile(filePath: string): Keypair {
 
 let PATH_TO_YOUR_SOLANA_PAYER_JSON   = process.env.PATH_TO_YOUR_SOLANA_PAYER_JSON!;
 let PATH_TO_YOUR_SOLANA_PLAYER2_JSON = process.env.PATH_TO_YOUR_SOLANA_PLAYER2_JSON!;
-let PATH_TO_YOUR_SOLANA_GAME_JSON    = process.env.PATH_TO_YOUR_SOLANA_GAME_JSON!;
 
 let DEPLOYED_PROGRAM_ADDRESS = process.env.DEPLOYED_PROGRAM_ADDRESS!;
 
 let PAYER_TOKEN_ACCOUNT    = process.env.PAYER_TOKEN_ACCOUNT!;
 let PLAYER2_TOKEN_ACCOUNT  = process.env.PLAYER2_TOKEN_ACCOUNT!;
-let SCROW_TOKEN_ACCOUNT    = process.env.SCROW_TOKEN_ACCOUNT!;
+let MINT                   = process.env.MINT!;
+// Token account of the config's treasury in MINT; the tests make the payer the treasury by default
+let TREASURY_TOKEN_ACCOUNT = process.env.TREASURY_TOKEN_ACCOUNT ?? PAYER_TOKEN_ACCOUNT;
 
-const TOKEN_PROGRAM_ID = new PublicKey("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+// Set TOKEN_PROGRAM to the Token-2022 program id when MINT is a Token-2022 mint
+const TOKEN_PROGRAM_ID = new PublicKey(process.env.TOKEN_PROGRAM ?? "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+const TOKEN_2022_PROGRAM_ID = new PublicKey("TokenzQdBNbLqP5VEhdkAS6EPFLC1PEnBqCXEpPxuEb");
+const ASSOCIATED_TOKEN_PROGRAM_ID = new PublicKey("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+const BPF_LOADER_UPGRADEABLE_PROGRAM_ID = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
 
 // Get oracles for mainnet here: https://www.pyth.network/developers/price-feed-ids#solana-stable
 // JBu1AL4obBcCMqKBBxhpWCNUt136ijcuMZLFvTP7iWdB (for Solana Mainnet)
 const usdcPriceAccount = new PublicKey("EdVCmQ9FSPcVe5YySXDPCRmc8aDQLKJ9xvYBMZPie1Vw"); // ETH/USDC Price Feed Account (Devnet)
 
+// Set MOCK_ORACLE=true when the deployed program was built with `--features mock-oracle`:
+// the tests then price the game through the payer's mock feed instead of Pyth
+const USE_MOCK_ORACLE = process.env.MOCK_ORACLE === "true";
+
 
 describe("Testing the escrow_program on the Solana devnet:", () => {
 
@@ -45,14 +55,41 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
 
     const payer = createKeypairFromFile(PATH_TO_YOUR_SOLANA_PAYER_JSON);
     const player2 = createKeypairFromFile(PATH_TO_YOUR_SOLANA_PLAYER2_JSON);
-    const escrowTokenAccountAuthority = createKeypairFromFile(PATH_TO_YOUR_SOLANA_GAME_JSON);
-    const gameAccount = Keypair.generate();
-    const escrowTokenAccount = new PublicKey(SCROW_TOKEN_ACCOUNT);
+    const mint = new PublicKey(MINT);
     const payerTokenAccount = new PublicKey(PAYER_TOKEN_ACCOUNT);
     const player2TokenAccount = new PublicKey(PLAYER2_TOKEN_ACCOUNT);
+    const treasuryTokenAccount = new PublicKey(TREASURY_TOKEN_ACCOUNT);
     const PROGRAM_ID: PublicKey = new PublicKey(
         DEPLOYED_PROGRAM_ADDRESS
     );
+    // The game account is a PDA of Player 1 and a nonce, so each run uses a fresh game
+    const gameNonce = BigInt(Date.now());
+    const stake = BigInt(1_000_000_000); // 1000 USDC, the mint has 6 decimals
+    const gameDuration = BigInt(24 * 60 * 60); // Unsettled games can be refunded as a draw after a day
+    const joinWindow = BigInt(60 * 60); // Player 2 has an hour to join before Player 1 can be refunded
+    const maxPriceAge = BigInt(60); // Oracle prices older than a minute are rejected
+    const maxConfidenceBps = 100; // Oracle prices with a confidence interval wider than 1% are rejected
+    const gameAccount = findGameAddress(PROGRAM_ID, payer.publicKey, gameNonce);
+    // The escrow token account is created by the program and owned by a PDA of the game
+    const escrowTokenAccount = getVaultAddress(PROGRAM_ID, gameAccount, mint);
+    // The feed pinned for the game when it is created
+    const oracleAccount = USE_MOCK_ORACLE ? findMockPriceAddress(PROGRAM_ID, payer.publicKey) : usdcPriceAccount;
+    const oracleType = USE_MOCK_ORACLE ? OracleType.Mock : OracleType.Pyth;
+    const registryAccount = findMarketRegistryAddress(PROGRAM_ID);
+    const configAccount = findConfigAddress(PROGRAM_ID);
+    const maxJoinDriftBps = 100; // Player 2 can join while the price is within 1% of the entry price
+    const winThresholdBps = 500; // A 5% move decides the game
+    // Id of the market priced by `oracleAccount`, registered by the first test if needed
+    let marketId = 0;
+
+    // Moves the mock feed to `price` (with 8 decimals); the live Pyth feed cannot be set
+    async function setOraclePrice(price: number) {
+        if (!USE_MOCK_ORACLE) {
+            return;
+        }
+        const instruction = setMockPriceInstruction(PROGRAM_ID, payer.publicKey, BigInt(Math.round(price * 100_000_000)), BigInt(0), -8);
+        await sendAndConfirmTransaction(connection, new Transaction().add(instruction), [payer]);
+    }
 
     class GameState {
         player1: Uint8Array;
@@ -61,10 +98,29 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
         player2_choice: boolean;
         entry_price: bigint;
         last_price: bigint;
-        game_active: boolean;
+        status: GameStatus;
         winner: Uint8Array;
-
-        constructor(fields: { player1: Uint8Array, player2: Uint8Array, player1_choice: boolean, player2_choice: boolean, entry_price: bigint, last_price: bigint, game_active: boolean, winner: Uint8Array } | undefined = undefined) {
+        escrow_token_account: Uint8Array;
+        vault_bump: number;
+        nonce: bigint;
+        bump: number;
+        stake: bigint;
+        mint: Uint8Array;
+        deposited: bigint;
+        expires_at: bigint;
+        join_deadline: bigint;
+        max_price_age: bigint;
+        max_confidence_bps: number;
+        price_expo: number;
+        oracle: Uint8Array;
+        oracle_program: Uint8Array;
+        oracle_type: number;
+        market_id: number;
+        max_join_drift_bps: number;
+        win_threshold_bps: number;
+        fee_bps: number;
+
+        constructor(fields: { player1: Uint8Array, player2: Uint8Array, player1_choice: boolean, player2_choice: boolean, entry_price: bigint, last_price: bigint, status: GameStatus, winner: Uint8Array, escrow_token_account: Uint8Array, vault_bump: number, nonce: bigint, bump: number, stake: bigint, mint: Uint8Array, deposited: bigint, expires_at: bigint, join_deadline: bigint, max_price_age: bigint, max_confidence_bps: number, price_expo: number, oracle: Uint8Array, oracle_program: Uint8Array, oracle_type: number, market_id: number, max_join_drift_bps: number, win_threshold_bps: number, fee_bps: number } | undefined = undefined) {
             if (fields) {
                 this.player1 = fields.player1;
                 this.player2 = fields.player2;
@@ -72,62 +128,224 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
                     this.player2_choice = fields.player2_choice,
                     this.entry_price = fields.entry_price;
                 this.last_price = fields.last_price;
-                this.game_active = fields.game_active;
+                this.status = fields.status;
                 this.winner = fields.winner;
+                this.escrow_token_account = fields.escrow_token_account;
+                this.vault_bump = fields.vault_bump;
+                this.nonce = fields.nonce;
+                this.bump = fields.bump;
+                this.stake = fields.stake;
+                this.mint = fields.mint;
+                this.deposited = fields.deposited;
+                this.expires_at = fields.expires_at;
+                this.join_deadline = fields.join_deadline;
+                this.max_price_age = fields.max_price_age;
+                this.max_confidence_bps = fields.max_confidence_bps;
+                this.price_expo = fields.price_expo;
+                this.oracle = fields.oracle;
+                this.oracle_program = fields.oracle_program;
+                this.oracle_type = fields.oracle_type;
+                this.market_id = fields.market_id;
+                this.max_join_drift_bps = fields.max_join_drift_bps;
+                this.win_threshold_bps = fields.win_threshold_bps;
+                this.fee_bps = fields.fee_bps;
             }
         }
     }
 
     const GameStateSchema = new Map([
-        [GameState, { kind: 'struct', fields: [['player1', [32]], ['player2', [32]], ['player1_choice', 'u8'], ['player2_choice', 'u8'], ['entry_price', 'u64'], ['last_price', 'u64'], ['game_active', 'u8'], ['winner', [32]]] }]
+        [GameState, { kind: 'struct', fields: [['player1', [32]], ['player2', [32]], ['player1_choice', 'u8'], ['player2_choice', 'u8'], ['entry_price', 'u64'], ['last_price', 'u64'], ['status', 'u8'], ['winner', [32]], ['escrow_token_account', [32]], ['vault_bump', 'u8'], ['nonce', 'u64'], ['bump', 'u8'], ['stake', 'u64'], ['mint', [32]], ['deposited', 'u64'], ['expires_at', 'i64'], ['join_deadline', 'i64'], ['max_price_age', 'u64'], ['max_confidence_bps', 'u16'], ['price_expo', 'i32'], ['oracle', [32]], ['oracle_program', [32]], ['oracle_type', 'u8'], ['market_id', 'u8'], ['max_join_drift_bps', 'u16'], ['win_threshold_bps', 'u16'], ['fee_bps', 'u16']] }]
     ]);
 
     function deserializeGameState(buffer: Buffer): GameState {
         return borsh.deserialize(GameStateSchema, GameState, buffer);
     }
 
-    it("Create game", async () => {
+    it("Program config", async () => {
         logSeparator();
+        // The first run on a deployment creates the config, which only the program's upgrade authority can do,
+        // with the payer as its admin and treasury and no timelock, so the updates queued by the tests can be applied straight away
+        if (await connection.getAccountInfo(configAccount) === null) {
+            const instruction = initializeConfigInstruction(PROGRAM_ID, payer.publicKey, maxJoinDriftBps, winThresholdBps, 0, payer.publicKey, BigInt(0));
+            await sendAndConfirmTransaction(connection, new Transaction().add(instruction), [payer]);
+            console.log("Program config created");
+        }
+        const config = parseConfig((await connection.getAccountInfo(configAccount))!.data);
+        console.log(`Program admin is ${config.admin.toBase58()}, paused: ${config.paused}, timelock: ${config.timelockDelay}s`);
+    });
 
-        const instruction_code = Buffer.from([0]);
+    it("Propose admin signed by someone other than the admin is rejected", async () => {
+        logSeparator();
+        const instruction = proposeAdminInstruction(PROGRAM_ID, player2.publicKey, player2.publicKey);
+        const transaction = new Transaction().add(instruction);
+        await expectRejected(connection, transaction, [player2], errorLog(EscrowErrorCode.Unauthorized));
+    });
 
-        const player1_choice = true;  // true- -> 'increase', false -> 'decrease'
+    it("Accept admin signed by someone other than the proposed admin is rejected", async () => {
+        logSeparator();
+        const config = parseConfig((await connection.getAccountInfo(configAccount))!.data);
+        if (!config.admin.equals(payer.publicKey)) {
+            console.log("Skipped: the payer is not the program admin");
+            return;
+        }
+        await sendAndConfirmTransaction(connection, new Transaction().add(proposeAdminInstruction(PROGRAM_ID, payer.publicKey, player2.publicKey)), [payer]);
+        try {
+            const transaction = new Transaction().add(acceptAdminInstruction(PROGRAM_ID, payer.publicKey));
+            await expectRejected(connection, transaction, [payer], errorLog(EscrowErrorCode.Unauthorized));
+        } finally {
+            // Proposing the default key withdraws the proposal
+            await sendAndConfirmTransaction(connection, new Transaction().add(proposeAdminInstruction(PROGRAM_ID, payer.publicKey, PublicKey.default)), [payer]);
+        }
+    });
 
-        //const entry_price = 0;
-        const entry_price = 2500;
+    it("Config update only applies once its timelock has elapsed", async () => {
+        logSeparator();
+        const config = parseConfig((await connection.getAccountInfo(configAccount))!.data);
+        if (!config.admin.equals(payer.publicKey)) {
+            console.log("Skipped: the payer is not the program admin");
+            return;
+        }
+        // Queue the settings already in force, so applying them changes nothing
+        const update = updateConfigInstruction(
+            PROGRAM_ID,
+            payer.publicKey,
+            config.allowedMints,
+            config.defaultMaxJoinDriftBps,
+            config.defaultWinThresholdBps,
+            config.feeBps,
+            config.treasury,
+            config.timelockDelay,
+        );
+        await sendAndConfirmTransaction(connection, new Transaction().add(update), [payer]);
+        if (!parseConfig((await connection.getAccountInfo(configAccount))!.data).hasPendingSettings) {
+            throw new Error("The config update was not queued");
+        }
 
-        // The price given by the oracle has 8 decimal places
-        const entry_price_in_micro_usdc = Math.round(entry_price * 100_000_000);
+        const apply = new Transaction().add(applyConfigUpdateInstruction(PROGRAM_ID));
+        if (config.timelockDelay > BigInt(0)) {
+            await expectRejected(connection, apply, [payer], errorLog(EscrowErrorCode.TimelockNotElapsed));
+            await sendAndConfirmTransaction(connection, new Transaction().add(cancelPendingUpdatesInstruction(PROGRAM_ID, payer.publicKey)), [payer]);
+        } else {
+            await sendAndConfirmTransaction(connection, apply, [payer]);
+        }
+        if (parseConfig((await connection.getAccountInfo(configAccount))!.data).hasPendingSettings) {
+            throw new Error("The config update is still queued");
+        }
+        console.log(`Test passed: the config update waited out the ${config.timelockDelay}s timelock`);
+    });
 
-        // Serialize the entry_price as u64 (8 bytes)
-        const entry_price_buffer = Buffer.alloc(8);
-        entry_price_buffer.writeBigUInt64LE(BigInt(entry_price_in_micro_usdc));
+    it("Apply config update with nothing queued is rejected", async () => {
+        logSeparator();
+        const transaction = new Transaction().add(applyConfigUpdateInstruction(PROGRAM_ID));
+        await expectRejected(connection, transaction, [payer], errorLog(EscrowErrorCode.NothingPending));
+    });
 
+    it("Pause signed by someone other than the admin is rejected", async () => {
+        logSeparator();
+        const instruction = setPausedInstruction(PROGRAM_ID, player2.publicKey, true);
+        const transaction = new Transaction().add(instruction);
+        await expectRejected(connection, transaction, [player2], errorLog(EscrowErrorCode.Unauthorized));
+    });
 
-        const player1_choice_buffer = Buffer.from([player1_choice ? 1 : 0]);  // Convert boolean to 1 or 0
+    it("Market registry", async () => {
+        logSeparator();
+        // The first run on a deployment creates the registry, which only the program's upgrade authority can do
+        if (await connection.getAccountInfo(registryAccount) === null) {
+            const instruction = initializeMarketRegistryInstruction(PROGRAM_ID, payer.publicKey);
+            await sendAndConfirmTransaction(connection, new Transaction().add(instruction), [payer]);
+            console.log("Market registry created");
+        }
 
-        // Concatenate the instruction_code, player1_choice, and entry_price buffers
-        const data = Buffer.concat([instruction_code, player1_choice_buffer, entry_price_buffer]);
+        const registry = parseMarketRegistry((await connection.getAccountInfo(registryAccount))!.data);
+        const existing = registry.markets.findIndex(market => market.active && market.oracle.equals(oracleAccount));
+        if (existing >= 0) {
+            marketId = existing;
+        } else {
+            // The mock feed must exist before it can be approved for a market
+            await setOraclePrice(2500);
+            const instruction = addMarketInstruction(PROGRAM_ID, payer.publicKey, oracleAccount, "ETH/USD", oracleType, maxJoinDriftBps, winThresholdBps);
+            await sendAndConfirmTransaction(connection, new Transaction().add(instruction), [payer]);
+            // The market is only registered once the config's timelock has elapsed, immediately on test deployments
+            await sendAndConfirmTransaction(connection, new Transaction().add(applyMarketUpdateInstruction(PROGRAM_ID)), [payer]);
+            marketId = registry.markets.length;
+            console.log("Market registered");
+        }
+        console.log(`Games are played on market ${marketId} priced by ${oracleAccount.toBase58()}`);
+    });
 
-        const instruction = new TransactionInstruction({
-            keys: [
-                { pubkey: payer.publicKey, isSigner: true, isWritable: true },  // Player 1 (payer)
-                { pubkey: gameAccount.publicKey, isSigner: true, isWritable: true },  // Escrow account for game state
-                { pubkey: escrowTokenAccount, isSigner: false, isWritable: true },  // Escrow token account to hold USDC
-                { pubkey: payerTokenAccount, isSigner: false, isWritable: true },  // Player 1's USDC token account
-                { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },  // Token program to transfer USDC
-                { pubkey: usdcPriceAccount, isSigner: false, isWritable: false }, // Pyth oracle
-                { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },  // System program
-            ],
-            programId: PROGRAM_ID,
-            data: data,
-        });
+    it("Add market signed by someone other than the admin is rejected", async () => {
+        logSeparator();
+        const instruction = addMarketInstruction(PROGRAM_ID, player2.publicKey, oracleAccount, "ETH/USD", oracleType, maxJoinDriftBps, winThresholdBps);
+        const transaction = new Transaction().add(instruction);
+        await expectRejected(connection, transaction, [player2], errorLog(EscrowErrorCode.Unauthorized));
+    });
+
+    it("Create game while the program is paused is rejected", async () => {
+        logSeparator();
+        const config = parseConfig((await connection.getAccountInfo(configAccount))!.data);
+        if (!config.admin.equals(payer.publicKey)) {
+            console.log("Skipped: the payer is not the program admin");
+            return;
+        }
+        await sendAndConfirmTransaction(connection, new Transaction().add(setPausedInstruction(PROGRAM_ID, payer.publicKey, true)), [payer]);
+        try {
+            const instruction = createGameInstruction(
+                PROGRAM_ID,
+                payer.publicKey,
+                mint,
+                payerTokenAccount,
+                oracleAccount,
+                true,
+                BigInt(0),
+                gameNonce + BigInt(5),
+                stake,
+                gameDuration,
+                joinWindow,
+                maxPriceAge,
+                maxConfidenceBps,
+                marketId,
+                0,
+                0,
+            );
+            const transaction = new Transaction().add(instruction);
+            await expectRejected(connection, transaction, [payer], errorLog(EscrowErrorCode.ProgramPaused));
+        } finally {
+            await sendAndConfirmTransaction(connection, new Transaction().add(setPausedInstruction(PROGRAM_ID, payer.publicKey, false)), [payer]);
+        }
+    });
+
+    it("Create game", async () => {
+        logSeparator();
+
+        const player1_choice = true;  // true- -> 'increase', false -> 'decrease'
+
+        // Prices are only read from the oracle; with the mock feed the entry price is 2500
+        await setOraclePrice(2500);
+
+        const instruction = createGameInstruction(
+            PROGRAM_ID,
+            payer.publicKey,
+            mint,
+            payerTokenAccount,
+            oracleAccount,
+            player1_choice,
+            BigInt(0),
+            gameNonce,
+            stake,
+            gameDuration,
+            joinWindow,
+            maxPriceAge,
+            maxConfidenceBps,
+            marketId,
+            0,
+            0,
+        );
 
         const transaction = new Transaction().add(instruction);
         const { blockhash } = await connection.getLatestBlockhash();
         transaction.recentBlockhash = blockhash;
         try {
-            await sendAndConfirmTransaction(connection, transaction, [payer, gameAccount]);
+            await sendAndConfirmTransaction(connection, transaction, [payer]);
         } catch (error) {
             console.error("Transaction failed with error:", error);
             if (error.logs) {
@@ -136,7 +354,7 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
         }
 
         // Fetch and validate the game state in the escrow account
-        const accountInfo = await connection.getAccountInfo(gameAccount.publicKey);
+        const accountInfo = await connection.getAccountInfo(gameAccount);
         const fundTokenBalance = await connection.getTokenAccountBalance(escrowTokenAccount);
 
 
@@ -153,31 +371,138 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
                 console.error("Test failed: Player 1 not set correctly");
             }
 
-            // Verify if the game is active
-            if (gameState.game_active == true) {
-                console.log("Test passed: Game is active");
+            // Verify the game is waiting for Player 2
+            if (gameState.status == GameStatus.Open) {
+                console.log("Test passed: Game is open");
             } else {
-                console.error("Test failed: Game is not active");
+                console.error(`Test failed: Game is ${GameStatus[gameState.status]}`);
             }
 
-            console.log(`Entry price is ${formatPrice(gameState.entry_price)} ETH/USDC`);
-            console.log(`Last price is ${formatPrice(gameState.last_price)} ETH/USDC`);
+            console.log(`Entry price is ${formatPrice(gameState.entry_price)} on market ${gameState.market_id}`);
+            console.log(`Last price is ${formatPrice(gameState.last_price)} on market ${gameState.market_id}`);
             
 
         }
         console.log(`Escrow Token Account Balance: ${fundTokenBalance.value.uiAmount} USDC`);
     });
 
+    it("Create game without Player 1 signature is rejected", async () => {
+        logSeparator();
+        const instruction = createGameInstruction(
+            PROGRAM_ID,
+            payer.publicKey,
+            mint,
+            payerTokenAccount,
+            oracleAccount,
+            true,
+            BigInt(0),
+            gameNonce + BigInt(1),
+            stake,
+            gameDuration,
+            joinWindow,
+            maxPriceAge,
+            maxConfidenceBps,
+            marketId,
+            0,
+            0,
+        );
+        instruction.keys[0].isSigner = false;
+
+        const transaction = new Transaction().add(instruction);
+        transaction.feePayer = player2.publicKey;
+        await expectRejected(connection, transaction, [player2], "missing required signature");
+    });
+
+    it("Create game with a price override is rejected", async () => {
+        logSeparator();
+        // Deployed builds never take the caller's price; only `test-price-override` builds accept one
+        const instruction = createGameInstruction(
+            PROGRAM_ID,
+            payer.publicKey,
+            mint,
+            payerTokenAccount,
+            oracleAccount,
+            true,
+            BigInt(2500 * 100_000_000),
+            gameNonce + BigInt(2),
+            stake,
+            gameDuration,
+            joinWindow,
+            maxPriceAge,
+            maxConfidenceBps,
+            marketId,
+            0,
+            0,
+        );
+
+        const transaction = new Transaction().add(instruction);
+        await expectRejected(connection, transaction, [payer], errorLog(EscrowErrorCode.PriceOverrideDisabled));
+    });
+
+    it("Create game with a win threshold above the program-wide bound is rejected", async () => {
+        logSeparator();
+        // Thresholds of 0 take the market's defaults; anything else must be within the bounds
+        const instruction = createGameInstruction(
+            PROGRAM_ID,
+            payer.publicKey,
+            mint,
+            payerTokenAccount,
+            oracleAccount,
+            true,
+            BigInt(0),
+            gameNonce + BigInt(4),
+            stake,
+            gameDuration,
+            joinWindow,
+            maxPriceAge,
+            maxConfidenceBps,
+            marketId,
+            0,
+            10_000,
+        );
+
+        const transaction = new Transaction().add(instruction);
+        await expectRejected(connection, transaction, [payer], errorLog(EscrowErrorCode.InvalidGameThresholds));
+    });
+
+    it("Create game with a feed other than the market's is rejected", async () => {
+        logSeparator();
+        // Any account other than the market's feed, here the stake mint
+        const instruction = createGameInstruction(
+            PROGRAM_ID,
+            payer.publicKey,
+            mint,
+            payerTokenAccount,
+            mint,
+            true,
+            BigInt(0),
+            gameNonce + BigInt(3),
+            stake,
+            gameDuration,
+            joinWindow,
+            maxPriceAge,
+            maxConfidenceBps,
+            marketId,
+            0,
+            0,
+        );
+
+        const transaction = new Transaction().add(instruction);
+        await expectRejected(connection, transaction, [payer], errorLog(EscrowErrorCode.OracleMismatch));
+    });
+
+    it("Oracle price from a feed other than the pinned one is rejected", async () => {
+        logSeparator();
+        // Any account other than the pinned feed, here the stake mint
+        const instruction = fetchPriceInstruction(PROGRAM_ID, mint, gameAccount);
+
+        const transaction = new Transaction().add(instruction);
+        await expectRejected(connection, transaction, [payer], errorLog(EscrowErrorCode.OracleMismatch));
+    });
+
     it('Oracle Price', async () => {
         logSeparator();
-        const instruction = new TransactionInstruction({
-            keys: [
-                { pubkey: usdcPriceAccount, isSigner: false, isWritable: false }, // Oráculo de precios
-                { pubkey: gameAccount.publicKey, isSigner: false, isWritable: true },  // Escrow account for game state
-            ],
-            programId: PROGRAM_ID,
-            data: Buffer.from([1]), 
-        });
+        const instruction = fetchPriceInstruction(PROGRAM_ID, oracleAccount, gameAccount);
 
         const transaction = new Transaction().add(instruction);
 
@@ -198,12 +523,17 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
                     const logs = transactionDetails.meta.logMessages;
 
                     // Find the log that contains the price
-                    const priceLog = logs.find(log => log.includes('Price of ETH/USDC:'));
+                    const priceLog = logs.find(log => log.includes('Price:'));
 
                     if (priceLog) {
-                        const price = priceLog.split('Price of ETH/USDC: ')[1]; // Extract the price from the log
+                        // The log has the feed's raw price; the game stores it normalised to 8 decimals
+                        const accountInfo = await connection.getAccountInfo(gameAccount);
+                        if (accountInfo === null) {
+                            throw new Error("Game account not found");
+                        }
+                        const gameState = deserializeGameState(accountInfo.data);
                         console.log("Test passed: Price successfully retrieved");
-                        console.log(`The price is: ${formatPrice(BigInt(price))} ETH/USDC`);
+                        console.log(`The price is: ${formatPrice(gameState.last_price)}`);
                     } else {
                         console.log('Price log not found');
                     }
@@ -212,6 +542,10 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
                 }
 
             } catch (error) {
+                if (error.logs && (hasErrorCode(error.logs, EscrowErrorCode.StalePrice) || hasErrorCode(error.logs, EscrowErrorCode.PriceTooUncertain))) {
+                    console.log("Oracle price rejected: the feed is stale or too uncertain for this game");
+                    return;
+                }
                 console.error("Transaction failed with error:", error);
                 if (error.logs) {
                     console.log("Transaction logs:", error.logs);
@@ -225,35 +559,96 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
     });
 
 
+    it("Join game without Player 2 signature is rejected", async () => {
+        logSeparator();
+        const instruction = joinGameInstruction(
+            PROGRAM_ID,
+            player2.publicKey,
+            gameAccount,
+            escrowTokenAccount,
+            mint,
+            player2TokenAccount,
+            oracleAccount,
+            BigInt(0),
+            stake,
+        );
+        instruction.keys[0].isSigner = false;
+
+        const transaction = new Transaction().add(instruction);
+        await expectRejected(connection, transaction, [payer], "missing required signature");
+    });
+
+    it("Join game with a different stake is rejected", async () => {
+        logSeparator();
+        const instruction = joinGameInstruction(
+            PROGRAM_ID,
+            player2.publicKey,
+            gameAccount,
+            escrowTokenAccount,
+            mint,
+            player2TokenAccount,
+            oracleAccount,
+            BigInt(0),
+            stake / BigInt(2),
+        );
+
+        const transaction = new Transaction().add(instruction);
+        await expectRejected(connection, transaction, [player2], errorLog(EscrowErrorCode.StakeMismatch));
+    });
+
+    it("Join game with a token program that does not own the mint is rejected", async () => {
+        logSeparator();
+        const instruction = joinGameInstruction(
+            PROGRAM_ID,
+            player2.publicKey,
+            gameAccount,
+            escrowTokenAccount,
+            mint,
+            player2TokenAccount,
+            oracleAccount,
+            BigInt(0),
+            stake,
+        );
+        // Swap in whichever token program is not the mint's owner
+        instruction.keys[5].pubkey = TOKEN_PROGRAM_ID.equals(TOKEN_2022_PROGRAM_ID)
+            ? new PublicKey("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA")
+            : TOKEN_2022_PROGRAM_ID;
+
+        const transaction = new Transaction().add(instruction);
+        await expectRejected(connection, transaction, [player2], errorLog(EscrowErrorCode.InvalidTokenProgram));
+    });
+
+    it("Refund before the join deadline is rejected", async () => {
+        logSeparator();
+        const instruction = refundUnjoinedInstruction(
+            PROGRAM_ID,
+            gameAccount,
+            escrowTokenAccount,
+            mint,
+            payerTokenAccount,
+            payer.publicKey,
+        );
+
+        const transaction = new Transaction().add(instruction);
+        transaction.feePayer = player2.publicKey;
+        await expectRejected(connection, transaction, [player2], errorLog(EscrowErrorCode.JoinDeadlineNotPassed));
+    });
+
     it("Join game", async () => {
         logSeparator();
-        const instruction_code = Buffer.from([2]);
-
-        //const last_price = 0;
-        const last_price = 2500;
-
-        // The price has 8 decimal places
-        const last_price_in_micro_usdc = Math.round(last_price * 100_000_000);
-
-        // Serialize the entry_price as u64 (8 bytes)
-        const last_price_buffer = Buffer.alloc(8);
-        last_price_buffer.writeBigUInt64LE(BigInt(last_price_in_micro_usdc));
-
-        const data = Buffer.concat([instruction_code, last_price_buffer]);
-        // Prepare the instruction for the join_game function
-        const instruction = new TransactionInstruction({
-            keys: [
-                { pubkey: player2.publicKey, isSigner: true, isWritable: true }, // Player 2
-                { pubkey: gameAccount.publicKey, isSigner: false, isWritable: true }, // Escrow account for game state
-                { pubkey: escrowTokenAccount, isSigner: false, isWritable: true },  // Escrow token account (holds USDC for both players)
-                { pubkey: player2TokenAccount, isSigner: false, isWritable: true }, // Player 2's USDC token account
-                { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },  // Token program to transfer USDC
-                { pubkey: usdcPriceAccount, isSigner: false, isWritable: false }, // Oráculo de precios
-                { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },  // System program
-            ],
-            programId: PROGRAM_ID,
-            data: data,
-        });
+        await setOraclePrice(2500);
+
+        const instruction = joinGameInstruction(
+            PROGRAM_ID,
+            player2.publicKey,
+            gameAccount,
+            escrowTokenAccount,
+            mint,
+            player2TokenAccount,
+            oracleAccount,
+            BigInt(0),
+            stake,
+        );
 
         const transaction = new Transaction().add(instruction);
         const { blockhash } = await connection.getLatestBlockhash();
@@ -272,7 +667,7 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
                 const logs = error.logs;
 
                 // Check for the specific log message related to price fluctuation
-                if (logs.some(log => log.includes("Impossible to join Player 2, price fluctuation more than 1%"))) {
+                if (hasErrorCode(logs, EscrowErrorCode.PriceFluctuationTooHigh)) {
                     console.log("Test valid: Player 2 could not join due to price fluctuation more than 1%");
                     // Treat this as a valid outcome and not a test failure
                     return;
@@ -287,7 +682,10 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
         }
 
         // Fetch the game account data again to check the state
-        const accountInfo = await connection.getAccountInfo(gameAccount.publicKey);
+        const accountInfo = await connection.getAccountInfo(gameAccount);
+        if (accountInfo === null) {
+            throw new Error("Game account not found");
+        }
         const gameState = deserializeGameState(accountInfo.data);
 
         if (new PublicKey(gameState.player2).equals(player2.publicKey)) {
@@ -296,10 +694,10 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
             console.error("Test failed: Player 2 not set correctly");
         }
 
-        if (gameState.game_active == true) {
-            console.log("Test passed: Game is active");
+        if (gameState.status == GameStatus.Matched) {
+            console.log("Test passed: Game is matched");
         } else {
-            console.error("Test failed: Game is not active");
+            console.error(`Test failed: Game is ${GameStatus[gameState.status]}`);
         }
 
         // Fetch the fund token account balance to verify deposit
@@ -312,43 +710,89 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
         console.log(`Player 1 Token Balance: ${player1Balance.value.uiAmount} USDC`);
         console.log(`Player 2 Token Balance: ${player2Balance.value.uiAmount} USDC`);
 
-        console.log(`Last price: ${formatPrice(gameState.last_price)} ETH/USDC`);
+        console.log(`Last price: ${formatPrice(gameState.last_price)}`);
 
 
     });
 
+    it("Refund draw before the game expires is rejected", async () => {
+        logSeparator();
+        const instruction = refundDrawInstruction(
+            PROGRAM_ID,
+            gameAccount,
+            escrowTokenAccount,
+            mint,
+            payerTokenAccount,
+            player2TokenAccount,
+            payer.publicKey,
+        );
+
+        const transaction = new Transaction().add(instruction);
+        await expectRejected(connection, transaction, [payer], errorLog(EscrowErrorCode.GameNotExpired));
+    });
+
+    it("Withdraw into a token account not owned by Player 1 is rejected", async () => {
+        logSeparator();
+        const instruction = withdrawFundsInstruction(
+            PROGRAM_ID,
+            payer.publicKey,
+            gameAccount,
+            escrowTokenAccount,
+            mint,
+            player2TokenAccount,
+        );
+
+        const transaction = new Transaction().add(instruction);
+        await expectRejected(connection, transaction, [payer], errorLog(EscrowErrorCode.TokenAccountOwnerMismatch));
+    });
+
+    it("Withdraw signed by someone other than Player 1 is rejected", async () => {
+        logSeparator();
+        const instruction = withdrawFundsInstruction(
+            PROGRAM_ID,
+            player2.publicKey,
+            gameAccount,
+            escrowTokenAccount,
+            mint,
+            player2TokenAccount,
+        );
+
+        const transaction = new Transaction().add(instruction);
+        await expectRejected(connection, transaction, [player2], errorLog(EscrowErrorCode.Unauthorized));
+    });
 
     it("Withdraw Game", async () => {
         logSeparator();
-        const instruction_code = 4;
-        const data = Buffer.from([instruction_code]);
-        const instruction = new TransactionInstruction({
-            keys: [
-                //{ pubkey: payer.publicKey, isSigner: true, isWritable: true },  // Player 1 (payer)
-                { pubkey: gameAccount.publicKey, isSigner: false, isWritable: true },  // Escrow account for game state
-                { pubkey: escrowTokenAccountAuthority.publicKey, isSigner: true, isWritable: true },
-                { pubkey: escrowTokenAccount, isSigner: false, isWritable: true },  // Escrow token account to hold USDC
-                { pubkey: payerTokenAccount, isSigner: false, isWritable: true },  // Player 1's USDC token account
-                { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },  // Token program to transfer USDC
-                { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },  // System program
-
-            ],
-            programId: PROGRAM_ID,  // Your Solana program ID
-            data: data,
-        });
+        const gameInfo = await connection.getAccountInfo(gameAccount);
+        if (gameInfo === null) {
+            console.log("Skipped: the game account is already closed");
+            return;
+        }
+        const rent = BigInt(gameInfo.lamports + (await connection.getAccountInfo(escrowTokenAccount))!.lamports);
+        const player1Before = BigInt(await connection.getBalance(payer.publicKey, 'confirmed'));
+
+        const instruction = withdrawFundsInstruction(
+            PROGRAM_ID,
+            payer.publicKey,
+            gameAccount,
+            escrowTokenAccount,
+            mint,
+            payerTokenAccount,
+        );
 
         const transaction = new Transaction().add(instruction);
         const { blockhash } = await connection.getLatestBlockhash();
         transaction.recentBlockhash = blockhash;
+        let signature: string | undefined;
         try {
-            await sendAndConfirmTransaction(connection, transaction, [escrowTokenAccountAuthority]);
+            signature = await sendAndConfirmTransaction(connection, transaction, [payer]);
             console.log("Test passed: Player 1 has successfully withdrawn funds");  // Success message
         } catch (error) {
             // Catch the error and check for logs
             if (error.logs) {
                 const logs = error.logs;
-                // Check if Player 2 already exists and log a custom message
-                if (logs.some(log => log.includes("Player 2 already exists"))) {
+                // Once Player 2 has joined the game is no longer open, so it cannot be cancelled
+                if (hasErrorCode(logs, EscrowErrorCode.InvalidGameStatus)) {
                     console.error("Test passed: Impossible to withdraw - Player 2 already exists, withdrawal not allowed, as expected");
                 } else {
                     console.error("Transaction logs:", logs);
@@ -358,22 +802,14 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
             }
         }
 
-        // Fetch and validate the game state in the escrow account
-        const accountInfo = await connection.getAccountInfo(gameAccount.publicKey);
-
-
-        const gameState = deserializeGameState(accountInfo.data);
-
-
-        if (gameState.game_active == false) {
-            console.log("Test passed: Now the game is inactive");
+        // A cancelled game's account and emptied vault are closed and their rent returned to Player 1
+        if (signature !== undefined) {
+            await expectClosedWithRentReturned(connection, signature, [gameAccount, escrowTokenAccount], payer.publicKey, player1Before, rent);
         } else {
-            console.error("Test passed: Game is still active");
-        }
+            const fundTokenBalance = await connection.getTokenAccountBalance(escrowTokenAccount);
 
-        const fundTokenBalance = await connection.getTokenAccountBalance(escrowTokenAccount);
-
-        console.log(`Escrow Token Account Balance: ${fundTokenBalance.value.uiAmount} USDC`);
+            console.log(`Escrow Token Account Balance: ${fundTokenBalance.value.uiAmount} USDC`);
+        }
 
         const player1Balance = await connection.getTokenAccountBalance(payerTokenAccount);
 
@@ -383,34 +819,17 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
 
         it("Settle game", async () => {
             logSeparator();
-            const instruction_code = Buffer.from([3]);
-
-            //const last_price = 0;
-            const last_price = 3000;
-
-            // The price has 8 decimal places
-            const last_price_in_micro_usdc = Math.round(last_price * 100_000_000);
-
-            // Serialize the entry_price as u64 (8 bytes)
-            const last_price_buffer = Buffer.alloc(8);
-            last_price_buffer.writeBigUInt64LE(BigInt(last_price_in_micro_usdc));
-
-            const data = Buffer.concat([instruction_code, last_price_buffer]);
-
-            // Prepare the instruction for the settle_game function
-            const instruction = new TransactionInstruction({
-                keys: [
-                    { pubkey: gameAccount.publicKey, isSigner: false, isWritable: true }, // Existing game account (escrow for game state, NOT lamports)
-                    { pubkey: payerTokenAccount, isSigner: false, isWritable: true },  // Player 1's token account (USDC)
-                    { pubkey: player2TokenAccount, isSigner: false, isWritable: true },  // Player 2's token account (USDC)
-                    { pubkey: usdcPriceAccount, isSigner: false, isWritable: false }, // Pyth Oracle
-                    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false }, // System program
-                    { pubkey: payer.publicKey, isSigner: true, isWritable: true },  // Player 1 (payer)
-                    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },  // Token program for SPL tokens
-                ],
-                programId: PROGRAM_ID,
-                data: data, // Data to trigger the `settle_game` instruction
-            });
+            // With the mock feed the price rises 20%, so Player 1 wins the increase bet
+            await setOraclePrice(3000);
+
+            const instruction = settleGameInstruction(
+                PROGRAM_ID,
+                gameAccount,
+                payerTokenAccount,
+                player2TokenAccount,
+                oracleAccount,
+                BigInt(0),
+            );
 
             const transaction = new Transaction().add(instruction);
             const { blockhash } = await connection.getLatestBlockhash();
@@ -428,13 +847,9 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
                 if (error.logs) {
                     const logs = error.logs;
 
-                    // Check if the logs contain the game inactive message
-                    if (logs.some(log => log.includes("Impossible to settle game, game is inactive"))) {
-                        console.log("Test passed: Game is inactive, settlement skipped as expected");
-                    }
-                    // Check if the logs contain the message about Player 2 missing
-                    else if (logs.some(log => log.includes("Impossible to settle game, there is not a player2"))) {
-                        console.log("Test passed: Player 2 is not present, settlement skipped as expected");
+                    // Only a matched game can be settled, e.g. not one that was cancelled or never joined
+                    if (hasErrorCode(logs, EscrowErrorCode.InvalidGameStatus)) {
+                        console.log("Test passed: Game is not matched, settlement skipped as expected");
                     }
                     else {
                         console.error("Transaction failed with unexpected error:", logs);
@@ -445,7 +860,11 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
             }
 
             // Fetch the game account data again to check the updated state
-            const accountInfo = await connection.getAccountInfo(gameAccount.publicKey);
+            const accountInfo = await connection.getAccountInfo(gameAccount);
+            if (accountInfo === null) {
+                console.log("Skipped: the game account is closed");
+                return;
+            }
             const gameState = deserializeGameState(accountInfo.data);
 
             // Check the USDC balances to confirm the winner
@@ -468,76 +887,153 @@ describe("Testing the escrow_program on the Solana devnet:", () => {
                 console.error("Test failed: Winner's public key does not match Player 1 or Player 2");
             }
 
-            if (gameState.game_active == false) {
-                console.log("Test passed: Now the game is inactive");
-            } else {
-                console.error("Game is still active");
-            }
+            console.log(`Game is ${GameStatus[gameState.status]}`);
             console.log(`Escrow Token Account Balance: ${escrowBalance.value.uiAmount} USDC`);
             console.log(`Player 1 Token Balance: ${player1Balance.value.uiAmount} USDC`);
             console.log(`Player 2 Token Balance: ${player2Balance.value.uiAmount} USDC`);
-            console.log(`Last price: ${formatPrice(gameState.last_price)} ETH/USDC`);
+            console.log(`Last price: ${formatPrice(gameState.last_price)}`);
 
         });
 
 
+    it("Close game paying a token account not owned by the winner is rejected", async () => {
+        logSeparator();
+        const accountInfo = await connection.getAccountInfo(gameAccount);
+        if (accountInfo === null) {
+            console.log("Skipped: the game account is closed");
+            return;
+        }
+        const gameState = deserializeGameState(accountInfo.data);
+        const winnerPubKey = new PublicKey(gameState.winner);
+        if (winnerPubKey.equals(PublicKey.default)) {
+            console.log("Skipped: there is no winner to check against");
+            return;
+        }
+        const loserTokenAccount = winnerPubKey.equals(payer.publicKey) ? player2TokenAccount : payerTokenAccount;
+
+        const instruction = closeGameInstruction(
+            PROGRAM_ID,
+            gameAccount,
+            escrowTokenAccount,
+            mint,
+            loserTokenAccount,
+            treasuryTokenAccount,
+            payer.publicKey,
+        );
+
+        const transaction = new Transaction().add(instruction);
+        await expectRejected(connection, transaction, [payer], errorLog(EscrowErrorCode.WinnerAccountMismatch));
+    });
+
+    it("Close game paying the fee to a token account not owned by the treasury is rejected", async () => {
+        logSeparator();
+        const accountInfo = await connection.getAccountInfo(gameAccount);
+        if (accountInfo === null) {
+            console.log("Skipped: the game account is closed");
+            return;
+        }
+        const gameState = deserializeGameState(accountInfo.data);
+        const winnerPubKey = new PublicKey(gameState.winner);
+        if (winnerPubKey.equals(PublicKey.default)) {
+            console.log("Skipped: there is no winner to pay");
+            return;
+        }
+        const winnerTokenAccount = winnerPubKey.equals(player2.publicKey) ? player2TokenAccount : payerTokenAccount;
+        const config = parseConfig((await connection.getAccountInfo(configAccount))!.data);
+        const otherTokenAccount = config.treasury.equals(payer.publicKey) ? player2TokenAccount : payerTokenAccount;
+
+        const instruction = closeGameInstruction(
+            PROGRAM_ID,
+            gameAccount,
+            escrowTokenAccount,
+            mint,
+            winnerTokenAccount,
+            otherTokenAccount,
+            payer.publicKey,
+        );
+
+        const transaction = new Transaction().add(instruction);
+        await expectRejected(connection, transaction, [payer], errorLog(EscrowErrorCode.TreasuryMismatch));
+    });
+
     it("Close game", async () => {
         logSeparator();
-        const instruction_code = Buffer.from([5]);
-
-        const data = Buffer.concat([instruction_code]);
-
-        const instruction = new TransactionInstruction({
-            keys: [
-                { pubkey: gameAccount.publicKey, isSigner: false, isWritable: true }, // Existing game account (escrow for game state, NOT lamports)
-                { pubkey: escrowTokenAccountAuthority.publicKey, isSigner: true, isWritable: true },  // Token account holding the USDC (Escrow token account)
-                { pubkey: escrowTokenAccount, isSigner: false, isWritable: true },  // Token account holding the USDC (Escrow token account)
-                { pubkey: payerTokenAccount, isSigner: false, isWritable: true },  // Player 1's token account (USDC)
-                { pubkey: player2TokenAccount, isSigner: false, isWritable: true },  // Player 2's token account (USDC)
-                { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },  // Token program for SPL tokens
-                { pubkey: payer.publicKey, isSigner: true, isWritable: true },  // Player 1 (payer)
-            ],
-            programId: PROGRAM_ID,
-            data: data, // Data to trigger the `settle_game` instruction
-        });
+        const accountInfo = await connection.getAccountInfo(gameAccount);
+        if (accountInfo === null) {
+            console.log("Skipped: the game account is already closed");
+            return;
+        }
+        const gameState = deserializeGameState(accountInfo.data);
+        const winnerTokenAccount = new PublicKey(gameState.winner).equals(player2.publicKey) ? player2TokenAccount : payerTokenAccount;
+
+        const instruction = closeGameInstruction(
+            PROGRAM_ID,
+            gameAccount,
+            escrowTokenAccount,
+            mint,
+            winnerTokenAccount,
+            treasuryTokenAccount,
+            payer.publicKey,
+        );
 
         const transaction = new Transaction().add(instruction);
         const { blockhash } = await connection.getLatestBlockhash();
         transaction.recentBlockhash = blockhash;
 
+        const rent = BigInt(accountInfo.lamports + (await connection.getAccountInfo(escrowTokenAccount))!.lamports);
+        const vaultBefore = BigInt((await connection.getTokenAccountBalance(escrowTokenAccount)).value.amount);
+        const winnerBefore = BigInt((await connection.getTokenAccountBalance(winnerTokenAccount)).value.amount);
+        const treasuryBefore = BigInt((await connection.getTokenAccountBalance(treasuryTokenAccount)).value.amount);
+        const player1Before = BigInt(await connection.getBalance(payer.publicKey, 'confirmed'));
+
+        let signature: string;
         try {
             // Send and confirm the transaction
-            await sendAndConfirmTransaction(
+            signature = await sendAndConfirmTransaction(
                 connection,
                 transaction,
-                [payer, escrowTokenAccountAuthority]  // Both players sign the transaction
+                [payer]
             );
         } catch (error) {
-            // Catch the error and handle the case when the game is inactive or Player 2 is missing
-            if (error.logs) {
-
-                //console.log("Transaction logs:", error.logs);
-                const logs = error.logs;
-                // Check if Player 2 already exists and log a custom message
-
-                if (logs.some(log => log.includes("Impossible to close game, game is still active"))) {
-                    console.error("Test passed: Impossible to close - No winner exists, payout not allowed, as expected.");
-                }
-                else if (logs.some(log => log.includes("Impossible to close game, there is no winner"))) {
-                    console.error("Test passed: Impossible to close - There is no winner, not allowed, as expected");
-                }
-                else if (logs.some(log => log.includes("Game closed successfully. Winner has been paid"))) {
-                    console.error("Test passed: Game closed successfully - Winner has been paid, as expected");
-                } 
-                
-                else {
-                    console.error("Transaction logs:", logs);
-                }
-
-            } else {
-                console.error("No logs available for this transaction");
+            // Only a settled game can be paid out, and only once; anything else is a failure
+            const logs: string[] = error.logs ?? [];
+            if (hasErrorCode(logs, EscrowErrorCode.InvalidGameStatus)) {
+                console.log("Skipped: Impossible to close - the game is not settled, payout not allowed, as expected");
+                return;
             }
+            console.error("Transaction logs:", logs);
+            throw error;
+        }
+
+        // The vault is split between the winner and the treasury, nothing is left behind or lost
+        // The emptied vault is closed along with the game
+        const vaultClosed = await connection.getAccountInfo(escrowTokenAccount) === null;
+        const vaultAfter = vaultClosed ? BigInt(0) : BigInt((await connection.getTokenAccountBalance(escrowTokenAccount)).value.amount);
+        const winnerAfter = BigInt((await connection.getTokenAccountBalance(winnerTokenAccount)).value.amount);
+        const treasuryAfter = BigInt((await connection.getTokenAccountBalance(treasuryTokenAccount)).value.amount);
+        const paidOut = winnerTokenAccount.equals(treasuryTokenAccount)
+            ? winnerAfter - winnerBefore
+            : (winnerAfter - winnerBefore) + (treasuryAfter - treasuryBefore);
+        if (vaultAfter != BigInt(0) || paidOut != vaultBefore) {
+            // A Token-2022 transfer fee withholds part of each transfer
+            throw new Error(`${paidOut} of ${vaultBefore} reached the winner and treasury, ${vaultAfter} left in the vault`);
         }
+        console.log(`Test passed: ${vaultBefore} paid out, with a ${gameState.fee_bps} bps fee to the treasury`);
+
+        // The game account is closed in the same transaction, so the fee charged is read from the payout log
+        const details = await connection.getTransaction(signature, { commitment: 'confirmed' });
+        const feeLog = details?.meta?.logMessages?.find(log => log.includes('Game closed successfully'));
+        const feeMatch = feeLog?.match(/fee (\d+)/);
+        const feeCharged = feeMatch ? BigInt(feeMatch[1]) : undefined;
+        const expectedFee = vaultBefore * BigInt(gameState.fee_bps) / BigInt(10000);
+        const treasuryReceived = winnerTokenAccount.equals(treasuryTokenAccount) ? expectedFee : treasuryAfter - treasuryBefore;
+        if (feeCharged !== expectedFee || treasuryReceived != feeCharged) {
+            throw new Error(`Fee charged ${feeCharged}, expected ${expectedFee}, treasury received ${treasuryReceived}`);
+        }
+        console.log(`Test passed: fee charged ${feeCharged} is ${gameState.fee_bps} bps of the vault and reached the treasury`);
+
+        // The paid-out game's account and vault are closed and their rent returned to Player 1
+        await expectClosedWithRentReturned(connection, signature, [gameAccount, escrowTokenAccount], payer.publicKey, player1Before, rent);
 
         // Check the USDC balances to confirm the winner
         const player1Balance = await connection.getTokenAccountBalance(payerTokenAccount);
@@ -559,4 +1055,702 @@ function formatPrice(price: bigint, decimals: number = 8): string {
 
 function logSeparator() {
     console.log('-------------------------------------------------');
-}
\ No newline at end of file
+}
+// Custom error codes returned by the program, mirroring `program/src/error.rs`.
+enum EscrowErrorCode {
+    InvalidInstruction = 0,
+    PriceFluctuationTooHigh = 1,
+    GameInactive = 2,
+    /** @deprecated never returned by the program */
+    GameStillActive = 3,
+    /** @deprecated never returned by the program */
+    Player2AlreadyJoined = 4,
+    /** @deprecated never returned by the program */
+    Player2Missing = 5,
+    /** @deprecated never returned by the program */
+    NoWinner = 6,
+    WinnerAccountMismatch = 7,
+    InvalidOracleAccount = 8,
+    PriceUnavailable = 9,
+    TokenAccountOwnerMismatch = 10,
+    EscrowTokenAccountMismatch = 11,
+    Unauthorized = 12,
+    InvalidVaultAuthority = 13,
+    InvalidGameAccount = 14,
+    StakeMismatch = 15,
+    InvalidStake = 16,
+    MintMismatch = 17,
+    InvalidTokenProgram = 18,
+    GameNotExpired = 19,
+    GameExpired = 20,
+    InvalidDuration = 21,
+    JoinDeadlinePassed = 22,
+    JoinDeadlineNotPassed = 23,
+    InvalidJoinWindow = 24,
+    StalePrice = 25,
+    PriceTooUncertain = 26,
+    InvalidOracleParameters = 27,
+    NonPositivePrice = 28,
+    OracleMismatch = 29,
+    PriceOverrideDisabled = 30,
+    InvalidMarket = 31,
+    MarketRegistryFull = 32,
+    InvalidMarketParameters = 33,
+    InvalidMarketRegistry = 34,
+    InvalidGameThresholds = 35,
+    ArithmeticOverflow = 36,
+    InvalidGameStatus = 37,
+    InvalidConfig = 38,
+    ProgramPaused = 39,
+    MintNotAllowed = 40,
+    InvalidConfigParameters = 41,
+    TreasuryMismatch = 42,
+    NothingPending = 43,
+    TimelockNotElapsed = 44,
+    RentRecipientMismatch = 45,
+    InvalidProgramData = 46,
+}
+
+function errorLog(code: EscrowErrorCode): string {
+    return `custom program error: 0x${code.toString(16)}`;
+}
+
+function hasErrorCode(logs: string[], code: EscrowErrorCode): boolean {
+    return logs.some(log => log.includes(errorLog(code)));
+}
+
+// Sends a transaction that must fail with a log line containing `expected`
+async function expectRejected(
+    connection: Connection,
+    transaction: Transaction,
+    signers: Keypair[],
+    expected: string,
+): Promise<void> {
+    try {
+        await sendAndConfirmTransaction(connection, transaction, signers);
+    } catch (error) {
+        const logs: string[] = error.logs ?? [];
+        if (logs.some(log => log.includes(expected))) {
+            console.log(`Test passed: transaction rejected with "${expected}"`);
+            return;
+        }
+        console.error("Transaction logs:", logs);
+        throw error;
+    }
+    throw new Error(`Transaction succeeded but was expected to fail with "${expected}"`);
+}
+
+// Checks that a finished game's account and vault are closed and that Player 1, who
+// also paid the transaction fee, got their rent back
+async function expectClosedWithRentReturned(
+    connection: Connection,
+    signature: string,
+    closedAccounts: PublicKey[],
+    player1: PublicKey,
+    player1Before: bigint,
+    rent: bigint,
+): Promise<void> {
+    for (const account of closedAccounts) {
+        if (await connection.getAccountInfo(account, 'confirmed') !== null) {
+            throw new Error(`Account ${account.toBase58()} is still open`);
+        }
+    }
+    const details = await connection.getTransaction(signature, { commitment: 'confirmed' });
+    const txFee = BigInt(details?.meta?.fee ?? 0);
+    const received = BigInt(await connection.getBalance(player1, 'confirmed')) - player1Before + txFee;
+    if (received !== rent) {
+        throw new Error(`Player 1 got ${received} lamports back, expected the ${rent} lamports of rent`);
+    }
+    console.log(`Test passed: game account and vault closed, ${rent} lamports of rent returned to Player 1`);
+}
+
+// Instruction builders mirroring `program/src/instruction.rs`.
+// The first data byte is the Borsh variant index of `EscrowInstruction`.
+enum EscrowInstruction {
+    CreateGame = 0,
+    FetchPrice = 1,
+    JoinGame = 2,
+    SettleGame = 3,
+    WithdrawFunds = 4,
+    CloseGame = 5,
+    RefundDraw = 6,
+    RefundUnjoined = 7,
+    SetMockPrice = 8,
+    InitializeMarketRegistry = 9,
+    AddMarket = 10,
+    UpdateMarket = 11,
+    InitializeConfig = 12,
+    UpdateConfig = 13,
+    SetPaused = 14,
+    ProposeAdmin = 15,
+    AcceptAdmin = 16,
+    ApplyConfigUpdate = 17,
+    ApplyMarketUpdate = 18,
+    CancelPendingUpdates = 19,
+}
+
+// Stages of a game's lifecycle, mirroring `program/src/state/game_status.rs`
+enum GameStatus {
+    Open = 0,
+    Matched = 1,
+    Settled = 2,
+    PaidOut = 3,
+    Cancelled = 4,
+    Expired = 5,
+    Drawn = 6,
+}
+
+// Kinds of price feed a game can be created with
+enum OracleType {
+    Pyth = 0,
+    Switchboard = 1,
+    Mock = 2,
+}
+
+function encodeU64(value: bigint): Buffer {
+    const buffer = Buffer.alloc(8);
+    buffer.writeBigUInt64LE(value);
+    return buffer;
+}
+
+function encodeU16(value: number): Buffer {
+    const buffer = Buffer.alloc(2);
+    buffer.writeUInt16LE(value);
+    return buffer;
+}
+
+function encodeI32(value: number): Buffer {
+    const buffer = Buffer.alloc(4);
+    buffer.writeInt32LE(value);
+    return buffer;
+}
+
+function encodeI64(value: bigint): Buffer {
+    const buffer = Buffer.alloc(8);
+    buffer.writeBigInt64LE(value);
+    return buffer;
+}
+
+function findMockPriceAddress(programId: PublicKey, authority: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync([Buffer.from("mock_price"), authority.toBuffer()], programId)[0];
+}
+
+function findConfigAddress(programId: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync([Buffer.from("config")], programId)[0];
+}
+
+// Admin, pause flag and settings in force of the `Config` account
+function parseConfig(data: Buffer): {
+    admin: PublicKey,
+    paused: boolean,
+    defaultMaxJoinDriftBps: number,
+    defaultWinThresholdBps: number,
+    feeBps: number,
+    treasury: PublicKey,
+    allowedMints: PublicKey[],
+    timelockDelay: bigint,
+    pendingAdmin: PublicKey,
+    hasPendingSettings: boolean,
+} {
+    // admin (32), bump (1), paused (1), then the settings: default_max_join_drift_bps (2),
+    // default_win_threshold_bps (2), fee_bps (2), treasury (32), allowed_mint_count (1),
+    // allowed_mints (8 * 32), timelock_delay (8); then pending_admin (32), pending_settings (303),
+    // pending_settings_at (8), has_pending_settings (1)
+    const allowedMints = [];
+    for (let i = 0; i < data[72]; i++) {
+        allowedMints.push(new PublicKey(data.subarray(73 + i * 32, 105 + i * 32)));
+    }
+    return {
+        admin: new PublicKey(data.subarray(0, 32)),
+        paused: data[33] === 1,
+        defaultMaxJoinDriftBps: data.readUInt16LE(34),
+        defaultWinThresholdBps: data.readUInt16LE(36),
+        feeBps: data.readUInt16LE(38),
+        treasury: new PublicKey(data.subarray(40, 72)),
+        allowedMints,
+        timelockDelay: data.readBigInt64LE(329),
+        pendingAdmin: new PublicKey(data.subarray(337, 369)),
+        hasPendingSettings: data[680] === 1,
+    };
+}
+
+// ProgramData account recording the program's upgrade authority
+function findProgramDataAddress(programId: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync([programId.toBuffer()], BPF_LOADER_UPGRADEABLE_PROGRAM_ID)[0];
+}
+
+function findMarketRegistryAddress(programId: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync([Buffer.from("markets")], programId)[0];
+}
+
+// Markets registered so far, decoded from the `MarketRegistry` account
+function parseMarketRegistry(data: Buffer): { markets: { symbol: string, oracle: PublicKey, active: boolean }[], hasPendingMarket: boolean } {
+    // bump (1), market_count (1), markets (16 * 46), pending_market_id (1), pending_market (46),
+    // pending_market_at (8), has_pending_market (1)
+    const marketCount = data[1];
+    const markets = [];
+    for (let id = 0; id < marketCount; id++) {
+        // symbol (8), oracle (32), oracle_type (1), max_join_drift_bps (2), win_threshold_bps (2), active (1)
+        const offset = 2 + id * 46;
+        markets.push({
+            symbol: data.subarray(offset, offset + 8).toString().replace(/\0+$/, ""),
+            oracle: new PublicKey(data.subarray(offset + 8, offset + 40)),
+            active: data[offset + 45] === 1,
+        });
+    }
+    return { markets, hasPendingMarket: data[793] === 1 };
+}
+
+function findGameAddress(programId: PublicKey, player1: PublicKey, nonce: bigint): PublicKey {
+    return PublicKey.findProgramAddressSync([Buffer.from("game"), player1.toBuffer(), encodeU64(nonce)], programId)[0];
+}
+
+function findVaultAuthority(programId: PublicKey, escrowAccount: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync([Buffer.from("vault"), escrowAccount.toBuffer()], programId)[0];
+}
+
+function getVaultAddress(programId: PublicKey, escrowAccount: PublicKey, mint: PublicKey): PublicKey {
+    return getAssociatedTokenAddressSync(mint, findVaultAuthority(programId, escrowAccount), true, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+}
+
+function createGameInstruction(
+    programId: PublicKey,
+    payer: PublicKey,
+    mint: PublicKey,
+    payerTokenAccount: PublicKey,
+    oracleAccount: PublicKey,
+    choice: boolean,
+    entryPrice: bigint,
+    nonce: bigint,
+    stake: bigint,
+    duration: bigint,
+    joinWindow: bigint,
+    maxPriceAge: bigint,
+    maxConfidenceBps: number,
+    marketId: number,
+    maxJoinDriftBps: number,
+    winThresholdBps: number,
+): TransactionInstruction {
+    const escrowAccount = findGameAddress(programId, payer, nonce);
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: payer, isSigner: true, isWritable: true },  // Player 1 (payer)
+            { pubkey: escrowAccount, isSigner: false, isWritable: true },  // Escrow account for game state
+            { pubkey: getVaultAddress(programId, escrowAccount, mint), isSigner: false, isWritable: true },  // Escrow token account to hold USDC
+            { pubkey: findVaultAuthority(programId, escrowAccount), isSigner: false, isWritable: false },  // Vault authority PDA
+            { pubkey: mint, isSigner: false, isWritable: false },  // Stake mint
+            { pubkey: payerTokenAccount, isSigner: false, isWritable: true },  // Player 1's USDC token account
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },  // Token program to transfer USDC
+            { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },  // Creates the vault
+            { pubkey: oracleAccount, isSigner: false, isWritable: false }, // Pyth oracle
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },  // System program
+            { pubkey: findMarketRegistryAddress(programId), isSigner: false, isWritable: false },  // Market registry
+            { pubkey: findConfigAddress(programId), isSigner: false, isWritable: false },  // Program config
+        ],
+        programId,
+        data: Buffer.concat([
+            Buffer.from([EscrowInstruction.CreateGame, choice ? 1 : 0]),
+            encodeU64(entryPrice),
+            encodeU64(nonce),
+            encodeU64(stake),
+            encodeI64(duration),
+            encodeI64(joinWindow),
+            encodeU64(maxPriceAge),
+            encodeU16(maxConfidenceBps),
+            Buffer.from([marketId]),
+            encodeU16(maxJoinDriftBps),
+            encodeU16(winThresholdBps),
+        ]),
+    });
+}
+
+function fetchPriceInstruction(
+    programId: PublicKey,
+    oracleAccount: PublicKey,
+    escrowAccount: PublicKey,
+): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: oracleAccount, isSigner: false, isWritable: false }, // Pyth oracle
+            { pubkey: escrowAccount, isSigner: false, isWritable: true },  // Escrow account for game state
+            { pubkey: findConfigAddress(programId), isSigner: false, isWritable: false },  // Program config
+        ],
+        programId,
+        data: Buffer.from([EscrowInstruction.FetchPrice]),
+    });
+}
+
+function joinGameInstruction(
+    programId: PublicKey,
+    player2: PublicKey,
+    escrowAccount: PublicKey,
+    escrowTokenAccount: PublicKey,
+    mint: PublicKey,
+    player2TokenAccount: PublicKey,
+    oracleAccount: PublicKey,
+    lastPrice: bigint,
+    stake: bigint,
+): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: player2, isSigner: true, isWritable: true }, // Player 2
+            { pubkey: escrowAccount, isSigner: false, isWritable: true }, // Escrow account for game state
+            { pubkey: escrowTokenAccount, isSigner: false, isWritable: true },  // Escrow token account (holds USDC for both players)
+            { pubkey: mint, isSigner: false, isWritable: false },  // Stake mint
+            { pubkey: player2TokenAccount, isSigner: false, isWritable: true }, // Player 2's USDC token account
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },  // Token program to transfer USDC
+            { pubkey: oracleAccount, isSigner: false, isWritable: false }, // Pyth oracle
+            { pubkey: findConfigAddress(programId), isSigner: false, isWritable: false },  // Program config
+        ],
+        programId,
+        data: Buffer.concat([Buffer.from([EscrowInstruction.JoinGame]), encodeU64(lastPrice), encodeU64(stake)]),
+    });
+}
+
+function settleGameInstruction(
+    programId: PublicKey,
+    escrowAccount: PublicKey,
+    player1TokenAccount: PublicKey,
+    player2TokenAccount: PublicKey,
+    oracleAccount: PublicKey,
+    lastPrice: bigint,
+): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: escrowAccount, isSigner: false, isWritable: true }, // Existing game account
+            { pubkey: player1TokenAccount, isSigner: false, isWritable: false },  // Player 1's token account (USDC)
+            { pubkey: player2TokenAccount, isSigner: false, isWritable: false },  // Player 2's token account (USDC)
+            { pubkey: oracleAccount, isSigner: false, isWritable: false }, // Pyth oracle
+            { pubkey: findConfigAddress(programId), isSigner: false, isWritable: false },  // Program config
+        ],
+        programId,
+        data: Buffer.concat([Buffer.from([EscrowInstruction.SettleGame]), encodeU64(lastPrice)]),
+    });
+}
+
+function withdrawFundsInstruction(
+    programId: PublicKey,
+    player1: PublicKey,
+    escrowAccount: PublicKey,
+    escrowTokenAccount: PublicKey,
+    mint: PublicKey,
+    player1TokenAccount: PublicKey,
+): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: player1, isSigner: true, isWritable: true },  // Player 1, gets the rent of the vault and game account back
+            { pubkey: escrowAccount, isSigner: false, isWritable: true },  // Escrow account for game state
+            { pubkey: findVaultAuthority(programId, escrowAccount), isSigner: false, isWritable: false },  // Vault authority PDA
+            { pubkey: escrowTokenAccount, isSigner: false, isWritable: true },  // Escrow token account holding USDC
+            { pubkey: mint, isSigner: false, isWritable: true },  // Stake mint, receives fees withheld in the vault
+            { pubkey: player1TokenAccount, isSigner: false, isWritable: true },  // Player 1's USDC token account
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },  // Token program to transfer USDC
+        ],
+        programId,
+        data: Buffer.from([EscrowInstruction.WithdrawFunds]),
+    });
+}
+
+function closeGameInstruction(
+    programId: PublicKey,
+    escrowAccount: PublicKey,
+    escrowTokenAccount: PublicKey,
+    mint: PublicKey,
+    winnerTokenAccount: PublicKey,
+    treasuryTokenAccount: PublicKey,
+    player1: PublicKey,
+): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: escrowAccount, isSigner: false, isWritable: true }, // Existing game account
+            { pubkey: findVaultAuthority(programId, escrowAccount), isSigner: false, isWritable: false },  // Vault authority PDA
+            { pubkey: escrowTokenAccount, isSigner: false, isWritable: true },  // Escrow token account holding USDC
+            { pubkey: mint, isSigner: false, isWritable: true },  // Stake mint, receives fees withheld in the vault
+            { pubkey: winnerTokenAccount, isSigner: false, isWritable: true },  // Winner's token account (USDC)
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },  // Token program for SPL tokens
+            { pubkey: findConfigAddress(programId), isSigner: false, isWritable: false },  // Program config
+            { pubkey: treasuryTokenAccount, isSigner: false, isWritable: true },  // Treasury's token account, receives the fee
+            { pubkey: player1, isSigner: false, isWritable: true },  // Player 1, gets the rent of the vault and game account back
+        ],
+        programId,
+        data: Buffer.from([EscrowInstruction.CloseGame]),
+    });
+}
+
+function refundDrawInstruction(
+    programId: PublicKey,
+    escrowAccount: PublicKey,
+    escrowTokenAccount: PublicKey,
+    mint: PublicKey,
+    player1TokenAccount: PublicKey,
+    player2TokenAccount: PublicKey,
+    player1: PublicKey,
+): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: escrowAccount, isSigner: false, isWritable: true }, // Existing game account
+            { pubkey: findVaultAuthority(programId, escrowAccount), isSigner: false, isWritable: false },  // Vault authority PDA
+            { pubkey: escrowTokenAccount, isSigner: false, isWritable: true },  // Escrow token account holding USDC
+            { pubkey: mint, isSigner: false, isWritable: true },  // Stake mint, receives fees withheld in the vault
+            { pubkey: player1TokenAccount, isSigner: false, isWritable: true },  // Player 1's token account (USDC)
+            { pubkey: player2TokenAccount, isSigner: false, isWritable: true },  // Player 2's token account (USDC)
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },  // Token program for SPL tokens
+            { pubkey: player1, isSigner: false, isWritable: true },  // Player 1, gets the rent of the vault and game account back
+        ],
+        programId,
+        data: Buffer.from([EscrowInstruction.RefundDraw]),
+    });
+}
+
+function refundUnjoinedInstruction(
+    programId: PublicKey,
+    escrowAccount: PublicKey,
+    escrowTokenAccount: PublicKey,
+    mint: PublicKey,
+    player1TokenAccount: PublicKey,
+    player1: PublicKey,
+): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: escrowAccount, isSigner: false, isWritable: true }, // Existing game account
+            { pubkey: findVaultAuthority(programId, escrowAccount), isSigner: false, isWritable: false },  // Vault authority PDA
+            { pubkey: escrowTokenAccount, isSigner: false, isWritable: true },  // Escrow token account holding USDC
+            { pubkey: mint, isSigner: false, isWritable: true },  // Stake mint, receives fees withheld in the vault
+            { pubkey: player1TokenAccount, isSigner: false, isWritable: true },  // Player 1's token account (USDC)
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },  // Token program for SPL tokens
+            { pubkey: player1, isSigner: false, isWritable: true },  // Player 1, gets the rent of the vault and game account back
+        ],
+        programId,
+        data: Buffer.from([EscrowInstruction.RefundUnjoined]),
+    });
+}
+
+function setMockPriceInstruction(
+    programId: PublicKey,
+    authority: PublicKey,
+    price: bigint,
+    conf: bigint,
+    expo: number,
+): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: authority, isSigner: true, isWritable: true },  // Feed authority, pays for the feed
+            { pubkey: findMockPriceAddress(programId, authority), isSigner: false, isWritable: true },  // Mock price feed
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },  // System program
+        ],
+        programId,
+        data: Buffer.concat([
+            Buffer.from([EscrowInstruction.SetMockPrice]),
+            encodeI64(price),
+            encodeU64(conf),
+            encodeI32(expo),
+        ]),
+    });
+}
+
+function initializeMarketRegistryInstruction(
+    programId: PublicKey,
+    upgradeAuthority: PublicKey,
+): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: upgradeAuthority, isSigner: true, isWritable: true },  // Upgrade authority, pays for the registry
+            { pubkey: findMarketRegistryAddress(programId), isSigner: false, isWritable: true },  // Market registry
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },  // System program
+            { pubkey: findProgramDataAddress(programId), isSigner: false, isWritable: false },  // ProgramData account
+        ],
+        programId,
+        data: Buffer.from([EscrowInstruction.InitializeMarketRegistry]),
+    });
+}
+
+function addMarketInstruction(
+    programId: PublicKey,
+    admin: PublicKey,
+    oracleAccount: PublicKey,
+    symbol: string,
+    oracleType: OracleType,
+    maxJoinDriftBps: number,
+    winThresholdBps: number,
+): TransactionInstruction {
+    const symbolBytes = Buffer.alloc(8);
+    symbolBytes.write(symbol);
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: admin, isSigner: true, isWritable: false },  // Admin
+            { pubkey: findMarketRegistryAddress(programId), isSigner: false, isWritable: true },  // Market registry
+            { pubkey: oracleAccount, isSigner: false, isWritable: false },  // Price feed of the market
+            { pubkey: findConfigAddress(programId), isSigner: false, isWritable: false },  // Program config
+        ],
+        programId,
+        data: Buffer.concat([
+            Buffer.from([EscrowInstruction.AddMarket]),
+            symbolBytes,
+            Buffer.from([oracleType]),
+            encodeU16(maxJoinDriftBps),
+            encodeU16(winThresholdBps),
+        ]),
+    });
+}
+
+function updateMarketInstruction(
+    programId: PublicKey,
+    admin: PublicKey,
+    marketId: number,
+    maxJoinDriftBps: number,
+    winThresholdBps: number,
+    active: boolean,
+): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: admin, isSigner: true, isWritable: false },  // Admin
+            { pubkey: findMarketRegistryAddress(programId), isSigner: false, isWritable: true },  // Market registry
+            { pubkey: findConfigAddress(programId), isSigner: false, isWritable: false },  // Program config
+        ],
+        programId,
+        data: Buffer.concat([
+            Buffer.from([EscrowInstruction.UpdateMarket, marketId]),
+            encodeU16(maxJoinDriftBps),
+            encodeU16(winThresholdBps),
+            Buffer.from([active ? 1 : 0]),
+        ]),
+    });
+}
+
+function initializeConfigInstruction(
+    programId: PublicKey,
+    upgradeAuthority: PublicKey,
+    defaultMaxJoinDriftBps: number,
+    defaultWinThresholdBps: number,
+    feeBps: number,
+    treasury: PublicKey,
+    timelockDelay: bigint,
+): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: upgradeAuthority, isSigner: true, isWritable: true },  // Upgrade authority, becomes the admin and pays for the config
+            { pubkey: findConfigAddress(programId), isSigner: false, isWritable: true },  // Program config
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },  // System program
+            { pubkey: findProgramDataAddress(programId), isSigner: false, isWritable: false },  // ProgramData account
+        ],
+        programId,
+        data: Buffer.concat([
+            Buffer.from([EscrowInstruction.InitializeConfig]),
+            encodeU16(defaultMaxJoinDriftBps),
+            encodeU16(defaultWinThresholdBps),
+            encodeU16(feeBps),
+            treasury.toBuffer(),
+            encodeI64(timelockDelay),
+        ]),
+    });
+}
+
+function updateConfigInstruction(
+    programId: PublicKey,
+    admin: PublicKey,
+    allowedMints: PublicKey[],
+    defaultMaxJoinDriftBps: number,
+    defaultWinThresholdBps: number,
+    feeBps: number,
+    treasury: PublicKey,
+    timelockDelay: bigint,
+): TransactionInstruction {
+    const mintCount = Buffer.alloc(4);
+    mintCount.writeUInt32LE(allowedMints.length);
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: admin, isSigner: true, isWritable: false },  // Admin
+            { pubkey: findConfigAddress(programId), isSigner: false, isWritable: true },  // Program config
+        ],
+        programId,
+        data: Buffer.concat([
+            Buffer.from([EscrowInstruction.UpdateConfig]),
+            mintCount,
+            ...allowedMints.map(mint => mint.toBuffer()),
+            encodeU16(defaultMaxJoinDriftBps),
+            encodeU16(defaultWinThresholdBps),
+            encodeU16(feeBps),
+            treasury.toBuffer(),
+            encodeI64(timelockDelay),
+        ]),
+    });
+}
+
+function setPausedInstruction(
+    programId: PublicKey,
+    admin: PublicKey,
+    paused: boolean,
+): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: admin, isSigner: true, isWritable: false },  // Admin
+            { pubkey: findConfigAddress(programId), isSigner: false, isWritable: true },  // Program config
+        ],
+        programId,
+        data: Buffer.from([EscrowInstruction.SetPaused, paused ? 1 : 0]),
+    });
+}
+
+function proposeAdminInstruction(
+    programId: PublicKey,
+    admin: PublicKey,
+    newAdmin: PublicKey,
+): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: admin, isSigner: true, isWritable: false },  // Admin
+            { pubkey: findConfigAddress(programId), isSigner: false, isWritable: true },  // Program config
+        ],
+        programId,
+        data: Buffer.concat([Buffer.from([EscrowInstruction.ProposeAdmin]), newAdmin.toBuffer()]),
+    });
+}
+
+function acceptAdminInstruction(
+    programId: PublicKey,
+    newAdmin: PublicKey,
+): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: newAdmin, isSigner: true, isWritable: false },  // Proposed admin
+            { pubkey: findConfigAddress(programId), isSigner: false, isWritable: true },  // Program config
+        ],
+        programId,
+        data: Buffer.from([EscrowInstruction.AcceptAdmin]),
+    });
+}
+
+function applyConfigUpdateInstruction(programId: PublicKey): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: findConfigAddress(programId), isSigner: false, isWritable: true },  // Program config
+        ],
+        programId,
+        data: Buffer.from([EscrowInstruction.ApplyConfigUpdate]),
+    });
+}
+
+function applyMarketUpdateInstruction(programId: PublicKey): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: findMarketRegistryAddress(programId), isSigner: false, isWritable: true },  // Market registry
+        ],
+        programId,
+        data: Buffer.from([EscrowInstruction.ApplyMarketUpdate]),
+    });
+}
+
+function cancelPendingUpdatesInstruction(
+    programId: PublicKey,
+    admin: PublicKey,
+): TransactionInstruction {
+    return new TransactionInstruction({
+        keys: [
+            { pubkey: admin, isSigner: true, isWritable: false },  // Admin
+            { pubkey: findConfigAddress(programId), isSigner: false, isWritable: true },  // Program config
+            { pubkey: findMarketRegistryAddress(programId), isSigner: false, isWritable: true },  // Market registry
+        ],
+        programId,
+        data: Buffer.from([EscrowInstruction.CancelPendingUpdates]),
+    });
+}